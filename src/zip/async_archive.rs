@@ -0,0 +1,333 @@
+use std::collections::HashMap;
+use std::io;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{ready, Context, Poll};
+use flate2::{Decompress, FlushDecompress, Status};
+use tokio::fs::File;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt, ReadBuf};
+use crate::zip::codec::CodecRegistry;
+use crate::zip::directory::{DirectoryParser, ParseStep};
+use crate::zip::limits::ZipLimits;
+use crate::zip::result::{ZipError, ZipResult};
+use crate::zip::spec;
+use crate::zip::spec::CompressionMethod;
+use crate::zip::storage::Storage;
+use crate::zip::zip_file::{find_reader, ZipFileData, ZipFileReader};
+
+const ASYNC_DEFLATE_BUF_SIZE: usize = 32 * 1024;
+
+pub struct AsyncZipArchive {
+    path: PathBuf,
+    pub entries: HashMap<String, Arc<ZipFileData>>,
+    pub limits: ZipLimits,
+    pub codecs: CodecRegistry,
+}
+
+impl AsyncZipArchive {
+    pub async fn new(path: impl AsRef<Path>) -> ZipResult<AsyncZipArchive> {
+        Self::with_limits(path, ZipLimits::default()).await
+    }
+
+    pub async fn with_limits(path: impl AsRef<Path>, limits: ZipLimits) -> ZipResult<AsyncZipArchive> {
+        let path = path.as_ref().to_path_buf();
+        let mut file = File::open(&path).await?;
+
+        let file_length = file.seek(SeekFrom::End(0)).await?;
+        let mut parser = DirectoryParser::new(file_length, limits.clone());
+        let mut step = parser.start();
+        let entries = loop {
+            match step {
+                ParseStep::NeedData { offset, len } => step = parser.feed(&read_range(&mut file, offset, len).await?)?,
+                ParseStep::Done(entries) => break entries,
+            }
+        };
+
+        Ok(AsyncZipArchive { path, entries, limits, codecs: CodecRegistry::default() })
+    }
+
+    pub fn file_exist(&self, name: &str) -> bool {
+        self.entries.contains_key(name)
+    }
+
+    // Every entry gets its own file handle, so entries can be read concurrently.
+    pub async fn by_name(&self, name: &str) -> ZipResult<AsyncZipFile> {
+        let data = self
+            .entries
+            .get(name)
+            .ok_or(ZipError::FileNotFound)?;
+
+        let method = data.compression_method;
+        if let CompressionMethod::Unsupported(method) = method {
+            if !self.codecs.contains(method) {
+                return Err(ZipError::UnsupportedEntry(name.to_string(), method));
+            }
+        }
+
+        let mut file = File::open(&self.path).await?;
+        file.seek(SeekFrom::Start(data.header_start)).await?;
+        let mut header = [0u8; 30];
+        file.read_exact(&mut header).await?;
+        if u32::from_le_bytes([header[0], header[1], header[2], header[3]]) != spec::LOCAL_FILE_HEADER_SIGNATURE {
+            return Err(ZipError::InvalidArchive("Invalid local file header"));
+        }
+        let file_name_length = u16::from_le_bytes([header[26], header[27]]) as u64;
+        let extra_field_length = u16::from_le_bytes([header[28], header[29]]) as u64;
+        let data_start = data.header_start + 30 + file_name_length + extra_field_length;
+        if data_start.saturating_add(data.compressed_size) > file.metadata().await?.len() {
+            return Err(ZipError::InvalidArchive("Entry data extends past the end of the archive"));
+        }
+        file.seek(SeekFrom::Start(data_start)).await?;
+
+        let builtin = !self.codecs.contains(method.to_u16());
+        let data_end = data_start + data.compressed_size;
+        let reader = match method {
+            CompressionMethod::Stored if builtin => {
+                AsyncZipFileReader::Stored(AsyncPlaintextReader::new(file, data_start, data_end))
+            }
+            CompressionMethod::Deflate if builtin => {
+                AsyncZipFileReader::Deflate(AsyncDeflateReader::new(AsyncPlaintextReader::new(file, data_start, data_end)))
+            }
+            // Other decoders only read synchronously, they get the compressed data from memory and
+            // share the dispatch of `ZipArchive`.
+            _ => {
+                let mut compressed = Vec::with_capacity(data.compressed_size as usize);
+                (&mut file).take(data.compressed_size).read_to_end(&mut compressed).await?;
+                let storage: Arc<dyn Storage> = Arc::new(compressed);
+                AsyncZipFileReader::Decoded(find_reader(&storage, &self.codecs, data, 0, 0)?)
+            }
+        };
+
+        let max_size = self.limits.bounds_output().then_some(data.uncompressed_size);
+
+        Ok(AsyncZipFile {
+            reader,
+            data: data.clone(),
+            max_size,
+            read_size: 0,
+        })
+    }
+}
+
+async fn read_range(file: &mut File, offset: u64, len: u64) -> ZipResult<Vec<u8>> {
+    if file.seek(SeekFrom::Start(offset)).await.is_err() {
+        return Err(ZipError::InvalidArchive("Could not seek to central directory"));
+    }
+    let mut buf = Vec::new();
+    file.take(len).read_to_end(&mut buf).await?;
+    Ok(buf)
+}
+
+pub struct AsyncZipFile {
+    reader: AsyncZipFileReader,
+    data: Arc<ZipFileData>,
+    max_size: Option<u64>,
+    read_size: u64,
+}
+
+impl AsyncZipFile {
+    pub fn data(&self) -> &ZipFileData {
+        &self.data
+    }
+}
+
+impl AsyncRead for AsyncZipFile {
+    fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        let before = buf.filled().len();
+        ready!(Pin::new(&mut this.reader).poll_read(cx, buf))?;
+        this.read_size += (buf.filled().len() - before) as u64;
+        if this.max_size.is_some_and(|max| this.read_size > max) {
+            return Poll::Ready(Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                ZipError::EntrySizeExceeded(this.data.file_name.clone()),
+            )));
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl AsyncSeek for AsyncZipFile {
+    fn start_seek(self: Pin<&mut Self>, position: SeekFrom) -> io::Result<()> {
+        Pin::new(&mut self.get_mut().reader).start_seek(position)
+    }
+
+    fn poll_complete(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+        let this = self.get_mut();
+        let position = ready!(Pin::new(&mut this.reader).poll_complete(cx))?;
+        this.read_size = position;
+        Poll::Ready(Ok(position))
+    }
+}
+
+pub enum AsyncZipFileReader {
+    Stored(AsyncPlaintextReader),
+    Deflate(AsyncDeflateReader),
+    // A synchronous decoder over compressed data that is already in memory, it never blocks on I/O.
+    Decoded(ZipFileReader),
+}
+
+impl AsyncRead for AsyncZipFileReader {
+    fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            AsyncZipFileReader::Stored(r) => Pin::new(r).poll_read(cx, buf),
+            AsyncZipFileReader::Deflate(r) => Pin::new(r).poll_read(cx, buf),
+            AsyncZipFileReader::Decoded(r) => {
+                let size = r.read(buf.initialize_unfilled())?;
+                buf.advance(size);
+                Poll::Ready(Ok(()))
+            }
+        }
+    }
+}
+
+impl AsyncSeek for AsyncZipFileReader {
+    fn start_seek(self: Pin<&mut Self>, position: SeekFrom) -> io::Result<()> {
+        match self.get_mut() {
+            AsyncZipFileReader::Stored(r) => Pin::new(r).start_seek(position),
+            AsyncZipFileReader::Deflate(r) => Pin::new(r).start_seek(position),
+            AsyncZipFileReader::Decoded(r) => r.seek(position).map(|_| ()),
+        }
+    }
+
+    fn poll_complete(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+        match self.get_mut() {
+            AsyncZipFileReader::Stored(r) => Pin::new(r).poll_complete(cx),
+            AsyncZipFileReader::Deflate(r) => Pin::new(r).poll_complete(cx),
+            AsyncZipFileReader::Decoded(r) => Poll::Ready(r.stream_position()),
+        }
+    }
+}
+
+pub struct AsyncPlaintextReader {
+    file: File,
+    start: u64,
+    end: u64,
+    position: u64,
+}
+
+impl AsyncPlaintextReader {
+    // `file` must already be positioned at `start`.
+    fn new(file: File, start: u64, end: u64) -> Self {
+        Self {
+            file,
+            start,
+            end,
+            position: 0,
+        }
+    }
+}
+
+impl AsyncRead for AsyncPlaintextReader {
+    fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        let len = this.end - this.start - this.position;
+        let limit = (len as usize).min(buf.remaining());
+        if limit == 0 {
+            return Poll::Ready(Ok(()));
+        }
+        let mut limited = ReadBuf::new(&mut buf.initialize_unfilled()[..limit]);
+        ready!(Pin::new(&mut this.file).poll_read(cx, &mut limited))?;
+        let size = limited.filled().len();
+        buf.advance(size);
+        this.position += size as u64;
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl AsyncSeek for AsyncPlaintextReader {
+    fn start_seek(self: Pin<&mut Self>, pos: SeekFrom) -> io::Result<()> {
+        let this = self.get_mut();
+        let position = match pos {
+            SeekFrom::Start(pos) => pos as i64,
+            SeekFrom::End(pos) => this.end as i64 - this.start as i64 + pos,
+            SeekFrom::Current(pos) => this.position as i64 + pos,
+        };
+        if position < 0 || position as u64 > this.end - this.start {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "Invalid seek input"));
+        }
+        Pin::new(&mut this.file).start_seek(SeekFrom::Start(this.start + position as u64))?;
+        this.position = position as u64;
+        Ok(())
+    }
+
+    fn poll_complete(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+        let this = self.get_mut();
+        ready!(Pin::new(&mut this.file).poll_complete(cx))?;
+        Poll::Ready(Ok(this.position))
+    }
+}
+
+pub struct AsyncDeflateReader {
+    inner: AsyncPlaintextReader,
+    decompress: Decompress,
+    deflate_buf: Box<[u8]>,
+    deflate_size: usize,
+    deflate_position: usize,
+}
+
+impl AsyncDeflateReader {
+    fn new(inner: AsyncPlaintextReader) -> Self {
+        Self {
+            inner,
+            decompress: Decompress::new(false),
+            deflate_buf: vec![0u8; ASYNC_DEFLATE_BUF_SIZE].into_boxed_slice(),
+            deflate_size: 0,
+            deflate_position: 0,
+        }
+    }
+}
+
+impl AsyncRead for AsyncDeflateReader {
+    fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        loop {
+            if this.deflate_position == this.deflate_size {
+                let mut input = ReadBuf::new(&mut this.deflate_buf);
+                ready!(Pin::new(&mut this.inner).poll_read(cx, &mut input))?;
+                this.deflate_size = input.filled().len();
+                this.deflate_position = 0;
+            }
+
+            let input = &this.deflate_buf[this.deflate_position..this.deflate_size];
+            let eof = input.is_empty();
+            let flush = if eof {
+                FlushDecompress::Finish
+            } else {
+                FlushDecompress::None
+            };
+
+            let output = buf.initialize_unfilled();
+            let before_out = this.decompress.total_out();
+            let before_in = this.decompress.total_in();
+            let ret = this.decompress.decompress(input, output, flush);
+            let read = (this.decompress.total_out() - before_out) as usize;
+            let consumed = (this.decompress.total_in() - before_in) as usize;
+            this.deflate_position += consumed;
+            buf.advance(read);
+
+            match ret {
+                Ok(Status::Ok | Status::BufError) if read == 0 && !eof && buf.remaining() > 0 => continue,
+                Ok(Status::Ok | Status::BufError | Status::StreamEnd) => return Poll::Ready(Ok(())),
+                Err(..) => {
+                    return Poll::Ready(Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        "corrupt deflate stream",
+                    )));
+                }
+            }
+        }
+    }
+}
+
+impl AsyncSeek for AsyncDeflateReader {
+    fn start_seek(self: Pin<&mut Self>, _position: SeekFrom) -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::Unsupported, "Seek is not supported for deflate entries"))
+    }
+
+    fn poll_complete(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+        Poll::Ready(Ok(self.decompress.total_out()))
+    }
+}
@@ -0,0 +1,106 @@
+use std::io;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use parking_lot::Mutex;
+use crate::zip::lru::Lru;
+use crate::zip::storage;
+use crate::zip::storage::Storage;
+
+const DEFAULT_BLOCK_SIZE: u64 = 64 * 1024;
+const DEFAULT_CAPACITY: usize = 256;
+
+#[derive(Clone, Debug)]
+pub struct BlockCacheOptions {
+    // Size of every read issued to the underlying storage; blocks are aligned to it.
+    pub block_size: u64,
+    // Number of blocks kept in memory, the least recently used block is dropped first.
+    pub capacity: usize,
+}
+
+impl Default for BlockCacheOptions {
+    fn default() -> Self {
+        Self {
+            block_size: DEFAULT_BLOCK_SIZE,
+            capacity: DEFAULT_CAPACITY,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+}
+
+// Serves reads smaller than a block from whole, aligned blocks of the underlying storage, so that
+// many small reads close to each other only reach it once.
+pub struct CachedStorage {
+    inner: Arc<dyn Storage>,
+    block_size: u64,
+    budget: usize,
+    lru: Mutex<Lru<u64>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl CachedStorage {
+    pub fn new(inner: Arc<dyn Storage>, options: BlockCacheOptions) -> Self {
+        Self {
+            inner,
+            block_size: options.block_size.max(1),
+            budget: options.capacity.saturating_mul(options.block_size.max(1) as usize),
+            lru: Mutex::new(Lru::new()),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+        }
+    }
+
+    pub fn clear(&self) {
+        *self.lru.lock() = Lru::new();
+    }
+
+    fn block(&self, index: u64) -> io::Result<Arc<[u8]>> {
+        if let Some(block) = self.lru.lock().get(&index) {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return Ok(block);
+        }
+        self.misses.fetch_add(1, Ordering::Relaxed);
+
+        // The lock is not held while reading, a slow storage must not stall readers of other blocks.
+        let block: Arc<[u8]> = storage::read_range(&*self.inner, index * self.block_size, self.block_size)?.into();
+        self.lru.lock().insert(index, block.clone(), self.budget);
+        Ok(block)
+    }
+}
+
+impl Storage for CachedStorage {
+    fn len(&self) -> io::Result<u64> {
+        self.inner.len()
+    }
+
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        // Reads of a block or more gain nothing from the cache and would only evict it, they go to
+        // the storage in one piece.
+        if buf.len() as u64 >= self.block_size {
+            return self.inner.read_at(buf, offset);
+        }
+        let block = self.block(offset / self.block_size)?;
+        let start = (offset % self.block_size) as usize;
+        if start >= block.len() {
+            return Ok(0);
+        }
+        let size = buf.len().min(block.len() - start);
+        buf[..size].copy_from_slice(&block[start..start + size]);
+        Ok(size)
+    }
+}
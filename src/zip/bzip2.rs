@@ -0,0 +1,27 @@
+use std::io::{Error, ErrorKind, Read, Seek, SeekFrom};
+use bzip2::read::BzDecoder;
+use crate::zip::plaintext::PlaintextReader;
+
+pub struct Bzip2Reader {
+    decoder: BzDecoder<PlaintextReader>,
+}
+
+impl Bzip2Reader {
+    pub fn new(reader: PlaintextReader) -> Self {
+        Self {
+            decoder: BzDecoder::new(reader),
+        }
+    }
+}
+
+impl Read for Bzip2Reader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.decoder.read(buf)
+    }
+}
+
+impl Seek for Bzip2Reader {
+    fn seek(&mut self, _pos: SeekFrom) -> std::io::Result<u64> {
+        Err(Error::new(ErrorKind::Unsupported, "Seek is not supported for bzip2 entries"))
+    }
+}
@@ -0,0 +1,40 @@
+use std::collections::HashMap;
+use std::io::{Error, ErrorKind, Read, Write};
+use std::sync::Arc;
+use crate::zip::zip_file::ZipFileData;
+
+pub trait Codec: Send + Sync {
+    fn decoder(&self, reader: Box<dyn Read + Send>, data: &ZipFileData) -> std::io::Result<Box<dyn Read + Send>>;
+
+    fn encoder(&self, _writer: Box<dyn Write + Send>) -> std::io::Result<Box<dyn Write + Send>> {
+        Err(Error::new(ErrorKind::Unsupported, "Codec does not support encoding"))
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct CodecRegistry {
+    codecs: HashMap<u16, Arc<dyn Codec>>,
+}
+
+impl CodecRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Registered codecs take precedence over the built-in ones for the same method id.
+    pub fn register(&mut self, method: u16, codec: impl Codec + 'static) {
+        self.codecs.insert(method, Arc::new(codec));
+    }
+
+    pub fn unregister(&mut self, method: u16) -> bool {
+        self.codecs.remove(&method).is_some()
+    }
+
+    pub fn contains(&self, method: u16) -> bool {
+        self.codecs.contains_key(&method)
+    }
+
+    pub fn get(&self, method: u16) -> Option<Arc<dyn Codec>> {
+        self.codecs.get(&method).cloned()
+    }
+}
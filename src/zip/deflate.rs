@@ -1,74 +1,89 @@
-use std::fs::File;
 use std::io::{Read, Seek, SeekFrom};
 use std::sync::Arc;
 use flate2::{Decompress, FlushDecompress, Status};
-use parking_lot::RwLock;
+use crate::zip::storage::Storage;
 
-const DEFLATE_BUF_SIZE: usize = 32;
+const DEFAULT_DEFLATE_BUF_SIZE: usize = 64 * 1024;
 
 pub struct DeflateReader {
-    file: Arc<RwLock<File>>,
+    storage: Arc<dyn Storage>,
+    // Compressed bytes fetched so far, relative to `start`.
     position: u64,
     start: u64,
     end: u64,
     decompress: Decompress,
-    deflate_buf: [u8; DEFLATE_BUF_SIZE],
+    deflate_buf: Box<[u8]>,
     deflate_size: usize,
     deflate_position: usize,
 }
 
 impl DeflateReader {
-    pub fn new(file: Arc<RwLock<File>>, start: u64, end: u64) -> Self {
+    // A `buf_size` of 0 uses `DEFAULT_DEFLATE_BUF_SIZE`.
+    pub fn new(storage: Arc<dyn Storage>, start: u64, end: u64, buf_size: usize) -> Self {
+        let buf_size = match buf_size {
+            0 => DEFAULT_DEFLATE_BUF_SIZE,
+            size => size,
+        };
+        // A buffer larger than the compressed data would never be filled.
+        let buf_size = (end - start).min(buf_size as u64) as usize;
         Self {
-            file,
+            storage,
             position: 0,
             start,
             end,
             decompress: Decompress::new(false),
-            deflate_buf: [0u8; DEFLATE_BUF_SIZE],
+            deflate_buf: vec![0u8; buf_size].into_boxed_slice(),
             deflate_size: 0,
             deflate_position: 0,
         }
     }
+
+    // Moves the unconsumed input to the front of the buffer and reads ahead as much compressed data
+    // as fits behind it, so the storage sees few large reads instead of many small ones.
+    fn fill(&mut self) -> std::io::Result<()> {
+        self.deflate_buf.copy_within(self.deflate_position..self.deflate_size, 0);
+        self.deflate_size -= self.deflate_position;
+        self.deflate_position = 0;
+
+        while self.deflate_size < self.deflate_buf.len() && self.position < self.end - self.start {
+            let limit = (self.end - self.start - self.position).min((self.deflate_buf.len() - self.deflate_size) as u64);
+            let buf = &mut self.deflate_buf[self.deflate_size..self.deflate_size + limit as usize];
+            let size = self.storage.read_at(buf, self.start + self.position)?;
+            if size == 0 {
+                break;
+            }
+            self.deflate_size += size;
+            self.position += size as u64;
+        }
+        Ok(())
+    }
 }
 
 impl Read for DeflateReader {
     fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
-        if self.position >= self.end - self.start {
-            return Ok(0);
-        }
         loop {
-            let (read, consumed, ret, eof);
-            {
-                if self.deflate_position == self.deflate_size {
-                    let from = self.position + self.start;
-                    let limit = (self.end - self.start - self.position).min(DEFLATE_BUF_SIZE as u64);
-                    {
-                        let mut file = &*self.file.write();
-                        file.seek(SeekFrom::Start(from))?;
-                        self.deflate_size = file.read(&mut self.deflate_buf[0..limit as usize])?;
-                    }
-                    self.deflate_position = 0;
-                }
+            if self.deflate_position == self.deflate_size {
+                self.fill()?;
+            }
 
-                let input = &self.deflate_buf[self.deflate_position..self.deflate_size];
+            let input = &self.deflate_buf[self.deflate_position..self.deflate_size];
 
-                eof = input.is_empty();
+            // Once all input is consumed the decompressor may still hold output, keep calling it with
+            // `Finish` until it has none left.
+            let eof = input.is_empty();
 
-                let before_out = self.decompress.total_out();
-                let before_in = self.decompress.total_in();
-                let flush = if eof {
-                    FlushDecompress::Finish
-                } else {
-                    FlushDecompress::None
-                };
+            let before_out = self.decompress.total_out();
+            let before_in = self.decompress.total_in();
+            let flush = if eof {
+                FlushDecompress::Finish
+            } else {
+                FlushDecompress::None
+            };
 
-                ret = self.decompress.decompress(input, buf, flush);
-                read = (self.decompress.total_out() - before_out) as usize;
-                consumed = (self.decompress.total_in() - before_in) as usize;
-            }
+            let ret = self.decompress.decompress(input, buf, flush);
+            let read = (self.decompress.total_out() - before_out) as usize;
+            let consumed = (self.decompress.total_in() - before_in) as usize;
             self.deflate_position += consumed;
-            self.position += consumed as u64;
 
             match ret {
                 Ok(Status::Ok | Status::BufError) if read == 0 && !eof && !buf.is_empty() => continue,
@@ -86,7 +101,7 @@ impl Read for DeflateReader {
 }
 
 impl Seek for DeflateReader {
-    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
-        todo!()
+    fn seek(&mut self, _pos: SeekFrom) -> std::io::Result<u64> {
+        Err(std::io::Error::new(std::io::ErrorKind::Unsupported, "Seek is not supported for deflate entries"))
     }
-}
\ No newline at end of file
+}
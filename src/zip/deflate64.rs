@@ -0,0 +1,27 @@
+use std::io::{BufReader, Error, ErrorKind, Read, Seek, SeekFrom};
+use deflate64::Deflate64Decoder;
+use crate::zip::plaintext::PlaintextReader;
+
+pub struct Deflate64Reader {
+    decoder: Deflate64Decoder<BufReader<PlaintextReader>>,
+}
+
+impl Deflate64Reader {
+    pub fn new(reader: PlaintextReader) -> Self {
+        Self {
+            decoder: Deflate64Decoder::new(reader),
+        }
+    }
+}
+
+impl Read for Deflate64Reader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.decoder.read(buf)
+    }
+}
+
+impl Seek for Deflate64Reader {
+    fn seek(&mut self, _pos: SeekFrom) -> std::io::Result<u64> {
+        Err(Error::new(ErrorKind::Unsupported, "Seek is not supported for deflate64 entries"))
+    }
+}
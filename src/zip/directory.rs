@@ -0,0 +1,356 @@
+use std::collections::HashMap;
+use std::io;
+use std::sync::Arc;
+use crate::zip::encoding::NameEncoding;
+use crate::zip::limits::ZipLimits;
+use crate::zip::result::{ZipError, ZipResult};
+use crate::zip::spec;
+use crate::zip::spec::Parsed;
+use crate::zip::zip_file::{parse_central_header, ZipFileData};
+
+pub type ZipEntries = HashMap<String, Arc<ZipFileData>>;
+
+pub enum ParseStep {
+    // The parser needs the bytes of `[offset, offset + len)` passed to `DirectoryParser::feed`.
+    NeedData { offset: u64, len: u64 },
+    Done(ZipEntries),
+}
+
+struct CentralDirectoryInfo {
+    archive_offset: u64,
+    directory_start: u64,
+    directory_size: u64,
+    number_of_files: usize,
+}
+
+enum State {
+    Tail { offset: u64 },
+    Zip64 { nominal_offset: u64, search_start: u64 },
+    Entries {
+        info: CentralDirectoryInfo,
+        // Absolute offset of the first byte in `pending`.
+        position: u64,
+        pending: Vec<u8>,
+        remaining: usize,
+        entries: ZipEntries,
+        total_size: u64,
+    },
+    Finished,
+}
+
+// Parses the central directory of an archive without doing any I/O itself, so that it can be
+// driven by any storage: start with `start` and keep feeding the requested ranges until `Done`.
+pub struct DirectoryParser {
+    file_length: u64,
+    limits: ZipLimits,
+    disks: Disks,
+    name_encoding: NameEncoding,
+    state: State,
+    archive_offset: Option<u64>,
+    shadowed: Vec<Arc<ZipFileData>>,
+}
+
+impl DirectoryParser {
+    pub fn new(file_length: u64, limits: ZipLimits) -> Self {
+        Self::with_disks(file_length, limits, vec![0])
+    }
+
+    // For split archives whose disks are stored back to back, `disk_offsets` holds the offset at
+    // which every disk starts. Offsets recorded in the archive are relative to their disk.
+    pub fn with_disks(file_length: u64, limits: ZipLimits, disk_offsets: Vec<u64>) -> Self {
+        Self {
+            file_length,
+            limits,
+            disks: Disks(disk_offsets),
+            name_encoding: NameEncoding::default(),
+            state: State::Finished,
+            archive_offset: None,
+            shadowed: Vec::new(),
+        }
+    }
+
+    // Size of the data prepended to the archive, all offsets recorded in the archive are relative to
+    // it. Known once the central directory has been located.
+    pub fn archive_offset(&self) -> Option<u64> {
+        self.archive_offset
+    }
+
+    // Entries that lost their place in the index to a later entry with different raw name bytes that
+    // decode to the same name. Known once parsing is done.
+    pub fn shadowed(&self) -> &[Arc<ZipFileData>] {
+        &self.shadowed
+    }
+
+    // Code page of names not flagged as UTF-8, set before parsing starts.
+    pub fn set_name_encoding(&mut self, name_encoding: NameEncoding) {
+        self.name_encoding = name_encoding;
+    }
+
+    // Once parsing is done `Auto` has been replaced by the encoding it picked.
+    pub fn name_encoding(&self) -> NameEncoding {
+        self.name_encoding
+    }
+
+    pub fn start(&mut self) -> ParseStep {
+        let len = spec::CentralDirectoryEnd::tail_len(self.file_length);
+        let offset = self.file_length - len;
+        self.state = State::Tail { offset };
+        ParseStep::NeedData { offset, len }
+    }
+
+    pub fn feed(&mut self, data: &[u8]) -> ZipResult<ParseStep> {
+        match std::mem::replace(&mut self.state, State::Finished) {
+            State::Tail { offset } => match locate(data, offset, &self.disks)? {
+                Location::Found(info) => self.read_directory(info),
+                Location::NeedZip64 { nominal_offset, search_start, locator_offset } => {
+                    self.state = State::Zip64 { nominal_offset, search_start };
+                    Ok(ParseStep::NeedData { offset: search_start, len: locator_offset - search_start })
+                }
+            },
+            State::Zip64 { nominal_offset, search_start } => {
+                let info = locate_zip64(data, search_start, nominal_offset, &self.disks, self.file_length)?;
+                self.read_directory(info)
+            }
+            State::Entries { info, position, mut pending, remaining, entries, total_size } => {
+                if data.is_empty() {
+                    return Err(ZipError::Io(io::ErrorKind::UnexpectedEof.into()));
+                }
+                pending.extend_from_slice(data);
+                self.parse_entries(info, position, pending, remaining, entries, total_size)
+            }
+            State::Finished => Err(ZipError::InvalidArchive("Central directory has already been parsed")),
+        }
+    }
+
+    fn read_directory(&mut self, info: CentralDirectoryInfo) -> ZipResult<ParseStep> {
+        self.limits.check_directory(info.number_of_files, info.directory_size)?;
+        self.archive_offset = Some(info.archive_offset);
+
+        if info.number_of_files == 0 {
+            return Ok(self.finish(HashMap::new()));
+        }
+
+        let offset = info.directory_start;
+        let len = info.directory_size.max(spec::CENTRAL_DIRECTORY_HEADER_SIZE as u64);
+        let file_capacity = info.number_of_files.min((len / spec::CENTRAL_DIRECTORY_HEADER_SIZE as u64) as usize);
+        self.state = State::Entries {
+            position: offset,
+            pending: Vec::new(),
+            remaining: info.number_of_files,
+            entries: HashMap::with_capacity(file_capacity),
+            total_size: 0,
+            info,
+        };
+        Ok(ParseStep::NeedData { offset, len })
+    }
+
+    fn parse_entries(
+        &mut self,
+        info: CentralDirectoryInfo,
+        mut position: u64,
+        mut pending: Vec<u8>,
+        mut remaining: usize,
+        mut entries: ZipEntries,
+        mut total_size: u64,
+    ) -> ZipResult<ParseStep> {
+        let mut consumed = 0;
+        while remaining > 0 {
+            match parse_central_header(&pending[consumed..], info.archive_offset, position)? {
+                Parsed::Complete(mut zip_file_data, size) => {
+                    zip_file_data.header_start = zip_file_data
+                        .header_start
+                        .checked_add(self.disks.offset(zip_file_data.disk_number)?)
+                        .ok_or(ZipError::InvalidArchive("Archive header is too large"))?;
+                    if zip_file_data.legacy_name && !matches!(self.name_encoding, NameEncoding::Cp437 | NameEncoding::Auto) {
+                        zip_file_data.file_name = self.name_encoding.decode(&zip_file_data.file_name_raw);
+                    }
+                    consumed += size;
+                    position += size as u64;
+                    remaining -= 1;
+                    self.limits.check_entry(&zip_file_data)?;
+                    total_size = total_size.saturating_add(zip_file_data.uncompressed_size);
+                    self.limits.check_total(total_size)?;
+                    insert_entry(&mut entries, &mut self.shadowed, Arc::new(zip_file_data));
+                }
+                Parsed::Incomplete(size) => {
+                    pending.drain(..consumed);
+                    let offset = position + pending.len() as u64;
+                    let len = (size - pending.len()) as u64;
+                    self.state = State::Entries { info, position, pending, remaining, entries, total_size };
+                    return Ok(ParseStep::NeedData { offset, len });
+                }
+            }
+        }
+        Ok(self.finish(entries))
+    }
+
+    // `Auto` can only pick an encoding once every name is known, the legacy names are then decoded
+    // again with it.
+    fn finish(&mut self, entries: ZipEntries) -> ParseStep {
+        if self.name_encoding != NameEncoding::Auto {
+            return ParseStep::Done(entries);
+        }
+        let legacy_names = entries.values().filter(|data| data.legacy_name).map(|data| data.file_name_raw.as_slice());
+        self.name_encoding = NameEncoding::detect(legacy_names);
+        if self.name_encoding == NameEncoding::Cp437 {
+            return ParseStep::Done(entries);
+        }
+
+        // Inserted again in archive order, so that the same entry wins as with any other encoding.
+        let mut all: Vec<Arc<ZipFileData>> = entries.into_values().chain(self.shadowed.drain(..)).collect();
+        all.sort_by_key(|data| data.header_start);
+        let mut decoded = HashMap::with_capacity(all.len());
+        for mut data in all {
+            if let Some(data) = Arc::get_mut(&mut data).filter(|data| data.legacy_name) {
+                data.file_name = self.name_encoding.decode(&data.file_name_raw);
+            }
+            insert_entry(&mut decoded, &mut self.shadowed, data);
+        }
+        ParseStep::Done(decoded)
+    }
+}
+
+// A name that repeats replaces the earlier entry. When the raw bytes differ, only the decoding made
+// the names equal and the earlier entry is kept aside in `shadowed`, so it stays reachable by its
+// raw name.
+fn insert_entry(entries: &mut ZipEntries, shadowed: &mut Vec<Arc<ZipFileData>>, data: Arc<ZipFileData>) {
+    if let Some(existing) = entries.insert(data.file_name.clone(), data) {
+        if existing.file_name_raw != entries[&existing.file_name].file_name_raw {
+            shadowed.push(existing);
+        }
+    }
+}
+
+// Logical offset at which every disk of an archive starts.
+struct Disks(Vec<u64>);
+
+impl Disks {
+    fn is_split(&self) -> bool {
+        self.0.len() > 1
+    }
+
+    // Entries of an archive read from a single storage all live on its only disk, whatever they claim.
+    fn offset(&self, disk: u32) -> ZipResult<u64> {
+        match self.0.as_slice() {
+            [offset] => Ok(*offset),
+            offsets => offsets.get(disk as usize).copied().ok_or(ZipError::MissingDisk(disk.saturating_add(1))),
+        }
+    }
+
+    fn check_last(&self, last_disk: u32) -> ZipResult<()> {
+        let disks = last_disk as usize + 1;
+        if disks == self.0.len() {
+            Ok(())
+        } else if !self.is_split() {
+            Err(ZipError::UnsupportedArchive)
+        } else if disks > self.0.len() {
+            // The last part given is the last disk, so the first one missing follows the others.
+            Err(ZipError::MissingDisk(self.0.len() as u32))
+        } else {
+            Err(ZipError::InvalidArchive("Archive has fewer disks than parts were given"))
+        }
+    }
+}
+
+enum Location {
+    Found(CentralDirectoryInfo),
+    NeedZip64 { nominal_offset: u64, search_start: u64, locator_offset: u64 },
+}
+
+fn locate(tail: &[u8], tail_offset: u64, disks: &Disks) -> ZipResult<Location> {
+    let (footer, cde_start_pos) = spec::CentralDirectoryEnd::find_and_parse_tail(tail, tail_offset)?;
+
+    let locator_start = cde_start_pos
+        .checked_sub(spec::ZIP64_CENTRAL_DIRECTORY_END_LOCATOR_SIZE)
+        .filter(|start| *start >= tail_offset);
+    let zip64locator = match locator_start {
+        Some(start) => match spec::Zip64CentralDirectoryEndLocator::parse_slice(&tail[(start - tail_offset) as usize..]) {
+            Ok(Parsed::Complete(loc, _)) => Some((start, loc)),
+            Ok(Parsed::Incomplete(_)) | Err(ZipError::InvalidArchive(_)) => None,
+            Err(e) => return Err(e),
+        },
+        None => None,
+    };
+
+    match zip64locator {
+        None => {
+            disks.check_last(footer.disk_number as u32)?;
+            let directory_disk = disks.offset(footer.disk_with_central_directory as u32)?;
+            let archive_offset = cde_start_pos
+                .checked_sub(footer.central_directory_size as u64)
+                .and_then(|x| x.checked_sub(footer.central_directory_offset as u64))
+                .and_then(|x| x.checked_sub(directory_disk))
+                .ok_or(ZipError::InvalidArchive(
+                    "Invalid central directory size or offset",
+                ))?;
+
+            // The count for this disk only covers all entries when there is a single disk.
+            let number_of_files = match disks.is_split() {
+                true => footer.number_of_files,
+                false => footer.number_of_files_on_this_disk,
+            };
+
+            Ok(Location::Found(CentralDirectoryInfo {
+                archive_offset,
+                directory_start: directory_disk + footer.central_directory_offset as u64 + archive_offset,
+                directory_size: footer.central_directory_size as u64,
+                number_of_files: number_of_files as usize,
+            }))
+        }
+        Some((locator_offset, locator64)) => {
+            disks.check_last(locator64.number_of_disks.saturating_sub(1))?;
+
+            let nominal_offset = disks
+                .offset(locator64.disk_with_central_directory)?
+                .checked_add(locator64.end_of_central_directory_offset)
+                .ok_or(ZipError::InvalidArchive("Invalid ZIP64 central directory end offset"))?;
+            if nominal_offset.saturating_add(spec::ZIP64_CENTRAL_DIRECTORY_END_SIZE) > locator_offset {
+                return Err(ZipError::InvalidArchive(
+                    "File cannot contain ZIP64 central directory end",
+                ));
+            }
+            let search_start = spec::Zip64CentralDirectoryEnd::search_start(nominal_offset, locator_offset);
+
+            Ok(Location::NeedZip64 { nominal_offset, search_start, locator_offset })
+        }
+    }
+}
+
+fn locate_zip64(
+    region: &[u8],
+    search_start: u64,
+    nominal_offset: u64,
+    disks: &Disks,
+    file_length: u64,
+) -> ZipResult<CentralDirectoryInfo> {
+    let (footer, record_offset) = spec::Zip64CentralDirectoryEnd::find_and_parse_slice(region, search_start)?;
+    // The search never starts before the nominal offset, anything in between was prepended.
+    let archive_offset = record_offset - nominal_offset;
+
+    disks.check_last(footer.disk_number)?;
+
+    let directory_start = disks
+        .offset(footer.disk_with_central_directory)?
+        .checked_add(footer.central_directory_offset)
+        .and_then(|x| x.checked_add(archive_offset))
+        .ok_or({
+            ZipError::InvalidArchive("Invalid central directory size or offset")
+        })?;
+    // The 64-bit fields are large enough to make anything up, hold them to what the file can contain.
+    if directory_start
+        .checked_add(footer.central_directory_size)
+        .is_none_or(|end| end > file_length)
+    {
+        return Err(ZipError::InvalidArchive("Invalid central directory size or offset"));
+    }
+    if footer.number_of_files > footer.central_directory_size / spec::CENTRAL_DIRECTORY_HEADER_SIZE as u64 {
+        return Err(ZipError::InvalidArchive("Central directory is too small for its number of entries"));
+    }
+
+    Ok(CentralDirectoryInfo {
+        archive_offset,
+        directory_start,
+        directory_size: footer.central_directory_size,
+        number_of_files: footer.number_of_files as usize,
+    })
+}
@@ -0,0 +1,129 @@
+use encoding_rs::{Encoding, BIG5, EUC_KR, GBK, IBM866, SHIFT_JIS, UTF_8, WINDOWS_1252};
+use crate::zip::cp437::FromCp437;
+
+// Code page of entry names that are not flagged as UTF-8. The format specifies CP437, but most
+// tools simply write whatever the system code page was.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum NameEncoding {
+    #[default]
+    Cp437,
+    Utf8,
+    ShiftJis,
+    Gbk,
+    Big5,
+    EucKr,
+    Cp866,
+    Windows1252,
+    // Picks UTF-8, Shift-JIS or GBK from the names of the archive, falling back to CP437.
+    Auto,
+}
+
+// Tried in this order by `Auto` once the names turn out not to be UTF-8, earlier ones win ties. Big5
+// and EUC-KR are left out, most GBK names decode to plausible text in them as well.
+const AUTO_CANDIDATES: [NameEncoding; 3] = [NameEncoding::Cp437, NameEncoding::ShiftJis, NameEncoding::Gbk];
+
+impl NameEncoding {
+    fn encoding(self) -> Option<&'static Encoding> {
+        match self {
+            NameEncoding::Cp437 | NameEncoding::Auto => None,
+            NameEncoding::Utf8 => Some(UTF_8),
+            NameEncoding::ShiftJis => Some(SHIFT_JIS),
+            NameEncoding::Gbk => Some(GBK),
+            NameEncoding::Big5 => Some(BIG5),
+            NameEncoding::EucKr => Some(EUC_KR),
+            NameEncoding::Cp866 => Some(IBM866),
+            NameEncoding::Windows1252 => Some(WINDOWS_1252),
+        }
+    }
+
+    // `Auto` has to be resolved with `detect` first, until then it decodes as CP437.
+    pub fn decode(self, bytes: &[u8]) -> String {
+        match self.encoding() {
+            Some(encoding) => encoding.decode_without_bom_handling(bytes).0.into_owned(),
+            None => bytes.from_cp437().into_owned(),
+        }
+    }
+
+    // Picks the encoding that decodes every name without errors and reads most like real text.
+    pub fn detect<'a>(names: impl IntoIterator<Item = &'a [u8]>) -> NameEncoding {
+        let names: Vec<&[u8]> = names.into_iter().filter(|name| !name.is_ascii()).collect();
+        if names.is_empty() {
+            return NameEncoding::Cp437;
+        }
+        if names.iter().all(|name| std::str::from_utf8(name).is_ok()) {
+            return NameEncoding::Utf8;
+        }
+
+        let mut best = (NameEncoding::Cp437, 0);
+        for candidate in AUTO_CANDIDATES {
+            let score = names
+                .iter()
+                .try_fold(0i64, |total, name| Some(total + candidate.score(name)?));
+            if let Some(score) = score.filter(|score| *score > best.1) {
+                best = (candidate, score);
+            }
+        }
+        best.0
+    }
+
+    // Kana and ideographs count for a name, half-width katakana and symbols against it. For GBK,
+    // characters outside the GB2312 range are rare in practice and mostly mean the bytes are
+    // something else.
+    fn score(self, name: &[u8]) -> Option<i64> {
+        if self == NameEncoding::Cp437 {
+            return Some(cp437_score(&name.from_cp437()));
+        }
+        let (text, had_errors) = self.encoding()?.decode_without_bom_handling(name);
+        if had_errors {
+            return None;
+        }
+        let mut score: i64 = text
+            .chars()
+            .map(|c| match c {
+                '\u{3040}'..='\u{30ff}' => 2,
+                '\u{4e00}'..='\u{9fff}' => 1,
+                '\u{ff61}'..='\u{ff9f}' => -2,
+                c if c.is_ascii() => 0,
+                _ => -1,
+            })
+            .sum();
+        if self == NameEncoding::Gbk {
+            score -= 2 * outside_gb2312(name);
+        }
+        Some(score)
+    }
+}
+
+// Accented letters next to ASCII letters, as in `Größe` or `über`, count for CP437. Box drawing and
+// the other symbols a CJK name turns into count against it.
+fn cp437_score(text: &str) -> i64 {
+    let chars: Vec<char> = text.chars().collect();
+    let is_ascii_letter = |i: Option<usize>| i.and_then(|i| chars.get(i)).is_some_and(|c| c.is_ascii_alphabetic());
+    (0..chars.len())
+        .filter(|i| !chars[*i].is_ascii())
+        .map(|i| {
+            let latin = ('\u{c0}'..='\u{ff}').contains(&chars[i]) && chars[i].is_alphabetic();
+            match latin && (is_ascii_letter(i.checked_sub(1)) || is_ascii_letter(Some(i + 1))) {
+                true => 1,
+                false => -1,
+            }
+        })
+        .sum()
+}
+
+fn outside_gb2312(name: &[u8]) -> i64 {
+    let mut count = 0;
+    let mut i = 0;
+    while i < name.len() {
+        if name[i] < 0x80 {
+            i += 1;
+            continue;
+        }
+        let trail = name.get(i + 1).copied().unwrap_or(0);
+        if !(0xA1..=0xF7).contains(&name[i]) || !(0xA1..=0xFE).contains(&trail) {
+            count += 1;
+        }
+        i += 2;
+    }
+    count
+}
@@ -0,0 +1,85 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use parking_lot::Mutex;
+use crate::zip::block_cache::CacheStats;
+use crate::zip::lru::Lru;
+
+// Archive id and entry name.
+type EntryKey = (u64, String);
+
+struct Shared {
+    budget: usize,
+    lru: Mutex<Lru<EntryKey>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+// Fully decompressed entries of recently used files, shared by every archive it is given to and
+// bounded by a budget in bytes. Entries larger than the budget are never cached.
+#[derive(Clone)]
+pub struct EntryCache {
+    shared: Arc<Shared>,
+}
+
+impl EntryCache {
+    pub fn new(budget: usize) -> Self {
+        Self {
+            shared: Arc::new(Shared {
+                budget,
+                lru: Mutex::new(Lru::new()),
+                hits: AtomicU64::new(0),
+                misses: AtomicU64::new(0),
+            }),
+        }
+    }
+
+    pub fn budget(&self) -> usize {
+        self.shared.budget
+    }
+
+    // Total size of the cached entries in bytes.
+    pub fn size(&self) -> usize {
+        self.shared.lru.lock().size()
+    }
+
+    pub fn len(&self) -> usize {
+        self.shared.lru.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.shared.hits.load(Ordering::Relaxed),
+            misses: self.shared.misses.load(Ordering::Relaxed),
+        }
+    }
+
+    pub fn clear(&self) {
+        *self.shared.lru.lock() = Lru::new();
+    }
+
+    pub(crate) fn fits(&self, size: u64) -> bool {
+        size <= self.shared.budget as u64
+    }
+
+    pub(crate) fn get(&self, archive: u64, name: &str) -> Option<Arc<[u8]>> {
+        let data = self.shared.lru.lock().get(&(archive, name.to_string()));
+        let counter = match data {
+            Some(_) => &self.shared.hits,
+            None => &self.shared.misses,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        data
+    }
+
+    pub(crate) fn insert(&self, archive: u64, name: &str, data: Arc<[u8]>) {
+        self.shared.lru.lock().insert((archive, name.to_string()), data, self.shared.budget);
+    }
+
+    pub(crate) fn remove_archive(&self, archive: u64) {
+        self.shared.lru.lock().retain(|(id, _)| *id != archive);
+    }
+}
@@ -0,0 +1,287 @@
+use std::collections::HashSet;
+use std::fs;
+use std::fs::File;
+use std::io;
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::thread;
+use crate::zip::result::{ZipError, ZipResult};
+use crate::zip::zip_archive::ZipArchive;
+use crate::zip::zip_file::ZipFileData;
+
+pub struct ExtractProgress<'a> {
+    pub entry: &'a ZipFileData,
+    pub entries_done: usize,
+    pub entries_total: usize,
+    pub bytes_done: u64,
+    pub bytes_total: u64,
+}
+
+pub type ProgressCallback = Arc<dyn Fn(&ExtractProgress) + Send + Sync>;
+
+#[derive(Clone, Copy, Default)]
+pub struct ExtractOptions {
+    // Restores setuid, setgid and sticky bits as well, by default only the permission bits are kept.
+    pub keep_special_bits: bool,
+}
+
+#[derive(Clone, Default)]
+pub struct ParallelExtractOptions {
+    // Number of worker threads, 0 uses the available parallelism.
+    pub threads: usize,
+    pub progress: Option<ProgressCallback>,
+    pub cancel: Option<Arc<AtomicBool>>,
+    pub extract: ExtractOptions,
+}
+
+struct ExtractPlan {
+    files: Vec<(PathBuf, Arc<ZipFileData>)>,
+    symlinks: Vec<(PathBuf, Arc<ZipFileData>)>,
+    directories: Vec<(PathBuf, Arc<ZipFileData>)>,
+}
+
+impl ZipArchive {
+    pub fn extract_to(&self, dir: impl AsRef<Path>) -> ZipResult<()> {
+        self.extract_to_with_options(dir, ExtractOptions::default())
+    }
+
+    pub fn extract_to_with_options(&self, dir: impl AsRef<Path>, options: ExtractOptions) -> ZipResult<()> {
+        let root = dir.as_ref();
+        let plan = self.plan_extract(root)?;
+        for (relative, data) in &plan.files {
+            self.extract_file(root, relative, data, options)?;
+        }
+        self.finish_extract(root, plan, options)
+    }
+
+    pub fn extract_to_parallel(&self, dir: impl AsRef<Path>, options: ParallelExtractOptions) -> ZipResult<()> {
+        let root = dir.as_ref();
+        let plan = self.plan_extract(root)?;
+
+        let threads = match options.threads {
+            0 => thread::available_parallelism().map_or(1, |n| n.get()),
+            n => n,
+        };
+        let entries_total = plan.files.len();
+        let bytes_total = plan.files.iter().map(|(_, data)| data.uncompressed_size).sum();
+        let next = AtomicUsize::new(0);
+        let entries_done = AtomicUsize::new(0);
+        let bytes_done = AtomicU64::new(0);
+        let failed = AtomicBool::new(false);
+
+        let worker = || -> ZipResult<()> {
+            loop {
+                if failed.load(Ordering::Relaxed) {
+                    return Ok(());
+                }
+                if options.cancel.as_ref().is_some_and(|cancel| cancel.load(Ordering::Relaxed)) {
+                    return Err(ZipError::Cancelled);
+                }
+                let Some((relative, data)) = plan.files.get(next.fetch_add(1, Ordering::Relaxed)) else {
+                    return Ok(());
+                };
+                if let Err(e) = self.extract_file(root, relative, data, options.extract) {
+                    failed.store(true, Ordering::Relaxed);
+                    return Err(e);
+                }
+                let entries = entries_done.fetch_add(1, Ordering::Relaxed) + 1;
+                let bytes = bytes_done.fetch_add(data.uncompressed_size, Ordering::Relaxed) + data.uncompressed_size;
+                if let Some(progress) = &options.progress {
+                    progress(&ExtractProgress {
+                        entry: data,
+                        entries_done: entries,
+                        entries_total,
+                        bytes_done: bytes,
+                        bytes_total,
+                    });
+                }
+            }
+        };
+
+        thread::scope(|scope| {
+            let handles: Vec<_> = (0..threads.min(entries_total).max(1))
+                .map(|_| scope.spawn(worker))
+                .collect();
+            handles
+                .into_iter()
+                .try_for_each(|handle| handle.join().expect("extract worker panicked"))
+        })?;
+
+        self.finish_extract(root, plan, options.extract)
+    }
+
+    // Validates every entry name and creates the directory tree up front, so the file phase
+    // never has to create directories concurrently.
+    fn plan_extract(&self, root: &Path) -> ZipResult<ExtractPlan> {
+        fs::create_dir_all(root)?;
+
+        let mut entries: Vec<Arc<ZipFileData>> = self.entries.values().cloned().collect();
+        entries.sort_by(|a, b| a.file_name.cmp(&b.file_name));
+
+        let mut plan = ExtractPlan {
+            files: Vec::new(),
+            symlinks: Vec::new(),
+            directories: Vec::new(),
+        };
+        for data in entries {
+            let relative = data
+                .enclosed_name()
+                .ok_or_else(|| ZipError::UnsafePath(data.file_name.clone()))?;
+            if data.is_dir() {
+                create_dir_in(root, &relative, &data.file_name)?;
+                plan.directories.push((relative, data));
+                continue;
+            }
+            if let Some(parent) = relative.parent() {
+                create_dir_in(root, parent, &data.file_name)?;
+            }
+            if data.is_symlink() {
+                plan.symlinks.push((relative, data));
+            } else {
+                plan.files.push((relative, data));
+            }
+        }
+        Ok(plan)
+    }
+
+    fn extract_file(&self, root: &Path, relative: &Path, data: &ZipFileData, options: ExtractOptions) -> ZipResult<()> {
+        let path = root.join(relative);
+        check_no_symlink(&path, &data.file_name)?;
+        let mut out = File::create(&path)?;
+        io::copy(&mut self.by_name(&data.file_name)?, &mut out)?;
+        restore_metadata(&out, &path, data, options)
+    }
+
+    fn finish_extract(&self, root: &Path, plan: ExtractPlan, options: ExtractOptions) -> ZipResult<()> {
+        // Links are created after all files so that no other entry can be written through them.
+        let links: HashSet<&Path> = plan.symlinks.iter().map(|(relative, _)| relative.as_path()).collect();
+        for (relative, data) in &plan.symlinks {
+            let mut target = String::new();
+            self.by_name(&data.file_name)?.read_to_string(&mut target)?;
+            if !link_target_is_enclosed(root, relative, &target, &links) {
+                return Err(ZipError::UnsafePath(data.file_name.clone()));
+            }
+            let path = root.join(relative);
+            check_no_symlink(&path, &data.file_name)?;
+            create_symlink(&target, &path)?;
+        }
+
+        // Directories last, both because writing files updates their modification time and because
+        // a read-only mode would prevent populating them.
+        for (relative, data) in plan.directories.iter().rev() {
+            let path = root.join(relative);
+            let dir = open_dir(&path)?;
+            restore_metadata(&dir, &path, data, options)?;
+        }
+
+        Ok(())
+    }
+}
+
+// Creates `relative` below `root`, refusing to follow any symlink already present on the way.
+fn create_dir_in(root: &Path, relative: &Path, name: &str) -> ZipResult<()> {
+    let mut path = root.to_path_buf();
+    for component in relative.components() {
+        path.push(component);
+        match fs::symlink_metadata(&path) {
+            Ok(metadata) if metadata.file_type().is_symlink() => {
+                return Err(ZipError::UnsafePath(name.to_string()));
+            }
+            Ok(metadata) if metadata.is_dir() => {}
+            Ok(_) => return Err(ZipError::InvalidArchive("Entry path collides with a file")),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => fs::create_dir(&path)?,
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(())
+}
+
+fn check_no_symlink(path: &Path, name: &str) -> ZipResult<()> {
+    match fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.file_type().is_symlink() => Err(ZipError::UnsafePath(name.to_string())),
+        _ => Ok(()),
+    }
+}
+
+// Resolves `target` lexically from the directory of `link`. Going through another link, whether
+// from the archive or already on disk, is refused: `c -> .` followed by `e -> c/..` would otherwise
+// let `e` point at the parent of `root`.
+fn link_target_is_enclosed(root: &Path, link: &Path, target: &str, links: &HashSet<&Path>) -> bool {
+    if target.is_empty() || target.contains('\0') {
+        return false;
+    }
+    let target = Path::new(target);
+    let mut resolved: Vec<Component> = link
+        .parent()
+        .map(|parent| parent.components().collect())
+        .unwrap_or_default();
+    let mut components = target.components().peekable();
+    while let Some(component) = components.next() {
+        match component {
+            Component::Normal(_) => {
+                resolved.push(component);
+                if components.peek().is_some() {
+                    let prefix: PathBuf = resolved.iter().collect();
+                    if links.contains(prefix.as_path()) || is_symlink(&root.join(&prefix)) {
+                        return false;
+                    }
+                }
+            }
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if resolved.pop().is_none() {
+                    return false;
+                }
+            }
+            Component::RootDir | Component::Prefix(_) => return false,
+        }
+    }
+    true
+}
+
+fn is_symlink(path: &Path) -> bool {
+    fs::symlink_metadata(path).is_ok_and(|metadata| metadata.file_type().is_symlink())
+}
+
+fn restore_metadata(file: &File, path: &Path, data: &ZipFileData, options: ExtractOptions) -> ZipResult<()> {
+    if let Some(modified) = data.last_modified() {
+        file.set_modified(modified)?;
+    }
+    #[cfg(unix)]
+    if let Some(mode) = data.unix_mode() {
+        use std::os::unix::fs::PermissionsExt;
+        let mask = if options.keep_special_bits { 0o7777 } else { 0o777 };
+        fs::set_permissions(path, fs::Permissions::from_mode(mode & mask))?;
+    }
+    #[cfg(not(unix))]
+    let _ = (path, options);
+    Ok(())
+}
+
+// Windows only opens directories with backup semantics.
+#[cfg(windows)]
+fn open_dir(path: &Path) -> io::Result<File> {
+    use std::os::windows::fs::OpenOptionsExt;
+    const FILE_FLAG_BACKUP_SEMANTICS: u32 = 0x0200_0000;
+    fs::OpenOptions::new()
+        .write(true)
+        .custom_flags(FILE_FLAG_BACKUP_SEMANTICS)
+        .open(path)
+}
+
+#[cfg(not(windows))]
+fn open_dir(path: &Path) -> io::Result<File> {
+    File::open(path)
+}
+
+#[cfg(unix)]
+fn create_symlink(target: &str, path: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(target, path)
+}
+
+#[cfg(not(unix))]
+fn create_symlink(target: &str, path: &Path) -> io::Result<()> {
+    fs::write(path, target)
+}
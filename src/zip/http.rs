@@ -0,0 +1,79 @@
+use std::io;
+use std::io::Read;
+use std::sync::Arc;
+use crate::zip::block_cache::BlockCacheOptions;
+use crate::zip::result::ZipResult;
+use crate::zip::storage::Storage;
+use crate::zip::zip_archive::{ZipArchive, ZipArchiveOptions};
+
+// An archive hosted on an HTTP server that supports range requests.
+pub struct HttpStorage {
+    agent: ureq::Agent,
+    url: String,
+    length: u64,
+}
+
+impl HttpStorage {
+    pub fn new(url: impl Into<String>) -> io::Result<Self> {
+        let agent = ureq::Agent::new();
+        let url = url.into();
+
+        // A one byte range both tells us the length and that the server honours ranges at all.
+        let response = range_request(&agent, &url, 0, 1)?;
+        let length = response
+            .header("Content-Range")
+            .and_then(|range| range.rsplit_once('/'))
+            .and_then(|(_, total)| total.parse().ok())
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "Server did not report the archive length"))?;
+
+        Ok(Self { agent, url, length })
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+}
+
+impl Storage for HttpStorage {
+    fn len(&self) -> io::Result<u64> {
+        Ok(self.length)
+    }
+
+    // Every call is a request of its own, put a `CachedStorage` in front to batch small reads.
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        if offset >= self.length || buf.is_empty() {
+            return Ok(0);
+        }
+        let len = (buf.len() as u64).min(self.length - offset);
+        let mut reader = range_request(&self.agent, &self.url, offset, len)?.into_reader().take(len);
+        let mut size = 0;
+        while size < len as usize {
+            match reader.read(&mut buf[size..len as usize])? {
+                0 => return Err(io::ErrorKind::UnexpectedEof.into()),
+                read => size += read,
+            }
+        }
+        Ok(size)
+    }
+}
+
+fn range_request(agent: &ureq::Agent, url: &str, start: u64, len: u64) -> io::Result<ureq::Response> {
+    let response = agent
+        .get(url)
+        .set("Range", &format!("bytes={}-{}", start, start + len - 1))
+        .call()
+        .map_err(io::Error::other)?;
+    if response.status() != 206 {
+        return Err(io::Error::new(io::ErrorKind::Unsupported, "Server does not support range requests"));
+    }
+    Ok(response)
+}
+
+impl ZipArchive {
+    // Remote archives are always read through a block cache, without one every small read of a
+    // decompressor would be a round trip.
+    pub fn from_url(url: &str, mut options: ZipArchiveOptions) -> ZipResult<ZipArchive> {
+        options.block_cache.get_or_insert_with(BlockCacheOptions::default);
+        Self::from_storage(Arc::new(HttpStorage::new(url)?), options)
+    }
+}
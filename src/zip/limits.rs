@@ -0,0 +1,64 @@
+use crate::zip::result::{ZipError, ZipResult};
+use crate::zip::zip_file::ZipFileData;
+
+#[derive(Clone, Debug, Default)]
+pub struct ZipLimits {
+    pub max_entries: Option<usize>,
+    pub max_central_directory_size: Option<u64>,
+    pub max_file_name_length: Option<usize>,
+    pub max_extra_field_length: Option<usize>,
+    pub max_entry_size: Option<u64>,
+    pub max_total_size: Option<u64>,
+    pub max_ratio: Option<u64>,
+}
+
+impl ZipLimits {
+    pub fn unlimited() -> Self {
+        Self::default()
+    }
+
+    // When any size limit is set, entries are not allowed to decompress past their declared size,
+    // otherwise the checks done against the central directory could be bypassed.
+    pub(crate) fn bounds_output(&self) -> bool {
+        self.max_entry_size.is_some() || self.max_total_size.is_some() || self.max_ratio.is_some()
+    }
+
+    pub(crate) fn check_directory(&self, number_of_files: usize, central_directory_size: u64) -> ZipResult<()> {
+        if self.max_entries.is_some_and(|max| number_of_files > max) {
+            return Err(ZipError::TooManyEntries(number_of_files));
+        }
+        if self.max_central_directory_size.is_some_and(|max| central_directory_size > max) {
+            return Err(ZipError::CentralDirectoryTooLarge(central_directory_size));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn check_entry(&self, data: &ZipFileData) -> ZipResult<()> {
+        if self.max_file_name_length.is_some_and(|max| data.file_name.len() > max) {
+            return Err(ZipError::FileNameTooLong(data.file_name.len()));
+        }
+        if self.max_extra_field_length.is_some_and(|max| data.extra_field.len() > max) {
+            return Err(ZipError::ExtraFieldTooLong(data.file_name.clone()));
+        }
+        if self.max_entry_size.is_some_and(|max| data.uncompressed_size > max) {
+            return Err(ZipError::EntryTooLarge(data.file_name.clone(), data.uncompressed_size));
+        }
+        if let Some(max) = self.max_ratio {
+            let too_high = match data.uncompressed_size.checked_div(data.compressed_size) {
+                Some(ratio) => ratio > max,
+                None => data.uncompressed_size > 0,
+            };
+            if too_high {
+                return Err(ZipError::CompressionRatioTooHigh(data.file_name.clone()));
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn check_total(&self, total_size: u64) -> ZipResult<()> {
+        if self.max_total_size.is_some_and(|max| total_size > max) {
+            return Err(ZipError::TotalSizeTooLarge(total_size));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,70 @@
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+use std::sync::Arc;
+
+// Byte buffers keyed by `K`, evicting the least recently used ones once their total size exceeds
+// the budget given to `insert`.
+pub struct Lru<K> {
+    items: HashMap<K, (Arc<[u8]>, u64)>,
+    // Last use tick of every item, oldest first.
+    order: BTreeMap<u64, K>,
+    tick: u64,
+    size: usize,
+}
+
+impl<K: Hash + Eq + Clone> Lru<K> {
+    pub fn new() -> Self {
+        Self {
+            items: HashMap::new(),
+            order: BTreeMap::new(),
+            tick: 0,
+            size: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn get(&mut self, key: &K) -> Option<Arc<[u8]>> {
+        let (value, last_use) = self.items.get_mut(key)?;
+        let key = self.order.remove(last_use)?;
+        self.tick += 1;
+        *last_use = self.tick;
+        self.order.insert(self.tick, key);
+        Some(value.clone())
+    }
+
+    pub fn insert(&mut self, key: K, value: Arc<[u8]>, budget: usize) {
+        self.tick += 1;
+        self.size += value.len();
+        if let Some((old, last_use)) = self.items.insert(key.clone(), (value, self.tick)) {
+            self.size -= old.len();
+            self.order.remove(&last_use);
+        }
+        self.order.insert(self.tick, key);
+        while self.size > budget {
+            let Some((_, oldest)) = self.order.pop_first() else { break };
+            if let Some((value, _)) = self.items.remove(&oldest) {
+                self.size -= value.len();
+            }
+        }
+    }
+
+    pub fn retain(&mut self, mut keep: impl FnMut(&K) -> bool) {
+        let size = &mut self.size;
+        self.items.retain(|key, (value, _)| {
+            let retained = keep(key);
+            if !retained {
+                *size -= value.len();
+            }
+            retained
+        });
+        let items = &self.items;
+        self.order.retain(|_, key| items.contains_key(key));
+    }
+}
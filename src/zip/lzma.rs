@@ -0,0 +1,43 @@
+use std::io::{Chain, Cursor, Error, ErrorKind, Read, Seek, SeekFrom};
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use xz2::read::XzDecoder;
+use xz2::stream::Stream;
+use crate::zip::plaintext::PlaintextReader;
+
+const LZMA_PROPERTIES_SIZE: u16 = 5;
+
+pub struct LzmaReader {
+    decoder: XzDecoder<Chain<Cursor<Vec<u8>>, PlaintextReader>>,
+}
+
+impl LzmaReader {
+    pub fn new(mut reader: PlaintextReader, uncompressed_size: u64) -> std::io::Result<Self> {
+        // Zip stores a 4 byte version/size header followed by the LZMA properties, while liblzma
+        // expects the .lzma header layout, so rebuild it with the size from the central directory.
+        let _version = reader.read_u16::<LittleEndian>()?;
+        let properties_size = reader.read_u16::<LittleEndian>()?;
+        if properties_size != LZMA_PROPERTIES_SIZE {
+            return Err(Error::new(ErrorKind::InvalidData, "Invalid LZMA properties size"));
+        }
+        let mut header = vec![0u8; LZMA_PROPERTIES_SIZE as usize];
+        reader.read_exact(&mut header)?;
+        header.write_u64::<LittleEndian>(uncompressed_size)?;
+
+        let stream = Stream::new_lzma_decoder(u64::MAX)?;
+        Ok(Self {
+            decoder: XzDecoder::new_stream(Cursor::new(header).chain(reader), stream),
+        })
+    }
+}
+
+impl Read for LzmaReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.decoder.read(buf)
+    }
+}
+
+impl Seek for LzmaReader {
+    fn seek(&mut self, _pos: SeekFrom) -> std::io::Result<u64> {
+        Err(Error::new(ErrorKind::Unsupported, "Seek is not supported for lzma entries"))
+    }
+}
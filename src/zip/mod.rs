@@ -1,8 +1,34 @@
+#[cfg(feature = "tokio")]
+pub mod async_archive;
+pub mod block_cache;
+#[cfg(feature = "bzip2")]
+mod bzip2;
+pub mod codec;
 mod cp437;
 mod deflate;
+#[cfg(feature = "deflate64")]
+mod deflate64;
+pub mod directory;
+pub mod encoding;
+pub mod entry_cache;
+pub mod extract;
+#[cfg(feature = "http")]
+pub mod http;
+pub mod limits;
+mod lru;
+#[cfg(feature = "lzma")]
+mod lzma;
 mod plaintext;
-mod result;
-mod spec;
+mod positional;
+pub mod result;
+pub mod spec;
+mod split;
+pub mod storage;
+#[cfg(feature = "xz")]
+mod xz;
+#[cfg(feature = "zstd")]
+mod zstd;
 
 pub mod zip_archive;
-pub mod zip_file;
\ No newline at end of file
+pub mod zip_file;
+pub mod zip_writer;
\ No newline at end of file
@@ -1,19 +1,18 @@
-use std::fs::File;
 use std::io::{Error, ErrorKind, Read, Seek, SeekFrom};
 use std::sync::Arc;
-use parking_lot::RwLock;
+use crate::zip::storage::Storage;
 
 pub struct PlaintextReader {
-    pub file: Arc<RwLock<File>>,
+    pub storage: Arc<dyn Storage>,
     pub start: u64,
     pub end: u64,
     pub position: u64,
 }
 
 impl PlaintextReader {
-    pub fn new(file: Arc<RwLock<File>>, start: u64, end: u64) -> Self {
+    pub fn new(storage: Arc<dyn Storage>, start: u64, end: u64) -> Self {
         Self {
-            file,
+            storage,
             start,
             end,
             position: 0,
@@ -29,11 +28,7 @@ impl Read for PlaintextReader {
         let from = self.position + self.start;
         let len = self.end - self.start - self.position;
         let limit = (len as usize).min(buf.len());
-        let mut size = {
-            let mut file = &*self.file.write();
-            file.seek(SeekFrom::Start(from))?;
-            file.read(&mut buf[0..limit])?
-        };
+        let size = self.storage.read_at(&mut buf[0..limit], from)?;
 
         self.position += size as u64;
 
@@ -43,16 +38,15 @@ impl Read for PlaintextReader {
 
 impl Seek for PlaintextReader {
     fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
-        let mut position = 0u64;
-        match pos {
-            SeekFrom::Start(pos) => position = pos,
-            SeekFrom::End(pos) => position = (self.end as i64 - self.start as i64 - pos) as u64,
-            SeekFrom::Current(pos) => position = (self.position as i64 + pos) as u64,
-        }
-        if position < 0 || position > self.end - self.start {
+        let position = match pos {
+            SeekFrom::Start(pos) => pos as i64,
+            SeekFrom::End(pos) => self.end as i64 - self.start as i64 + pos,
+            SeekFrom::Current(pos) => self.position as i64 + pos,
+        };
+        if position < 0 || position as u64 > self.end - self.start {
             return Err(Error::new(ErrorKind::InvalidInput, "Invalid seek input"));
         }
-        self.position = position;
+        self.position = position as u64;
         Ok(self.position)
     }
 }
\ No newline at end of file
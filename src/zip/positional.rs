@@ -0,0 +1,14 @@
+use std::fs::File;
+use std::io;
+
+// Reads at an absolute offset without touching the shared file cursor, so concurrent readers don't
+// need to lock the file.
+#[cfg(unix)]
+pub fn read_at(file: &File, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+    std::os::unix::fs::FileExt::read_at(file, buf, offset)
+}
+
+#[cfg(windows)]
+pub fn read_at(file: &File, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+    std::os::windows::fs::FileExt::seek_read(file, buf, offset)
+}
@@ -11,15 +11,55 @@ pub enum ZipError {
     #[error("{}", .0)]
     InvalidArchive(&'static str),
 
-    #[error("Support for multi - disk files is not implemented")]
+    #[error("Archive is split over several disks, open it with all of its parts")]
     UnsupportedArchive,
 
+    // Numbered from 1, like the `.z01` files of a split archive.
+    #[error("Split archive is missing disk {}", .0)]
+    MissingDisk(u32),
+
     #[error("AES extra data field has an unsupported length")]
     UnsupportedAesExtraData,
 
     #[error("UnsupportedCompressionMethod {}", .0)]
     UnsupportedCompressionMethod(u16),
 
+    #[error("Entry {} uses unsupported compression method {}", .0, .1)]
+    UnsupportedEntry(String, u16),
+
+    #[error("Archive has {} entries, more than the configured limit", .0)]
+    TooManyEntries(usize),
+
+    #[error("Central directory is {} bytes, more than the configured limit", .0)]
+    CentralDirectoryTooLarge(u64),
+
+    #[error("File name is {} bytes, more than the configured limit", .0)]
+    FileNameTooLong(usize),
+
+    #[error("Entry {} has an extra field larger than the configured limit", .0)]
+    ExtraFieldTooLong(String),
+
+    #[error("Entry {} is {} bytes uncompressed, more than the configured limit", .0, .1)]
+    EntryTooLarge(String, u64),
+
+    #[error("Archive is {} bytes uncompressed, more than the configured limit", .0)]
+    TotalSizeTooLarge(u64),
+
+    #[error("Entry {} has a compression ratio higher than the configured limit", .0)]
+    CompressionRatioTooHigh(String),
+
+    #[error("Entry {} is 4 GiB or larger and was not started as a large file", .0)]
+    LargeFileRequired(String),
+
+    #[error("Entry {} decompressed past its declared size", .0)]
+    EntrySizeExceeded(String),
+
+    #[error("Entry {} has a path that escapes the extraction directory", .0)]
+    UnsafePath(String),
+
+    #[error("Operation was cancelled")]
+    Cancelled,
+
     #[error("FileNotFound")]
     FileNotFound,
 }
\ No newline at end of file
@@ -1,17 +1,53 @@
 use std::{fmt, io};
-use std::io::{Read};
-use byteorder::{LittleEndian, ReadBytesExt};
+use std::io::{Read, Seek, Write};
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
 use crate::zip::result::{ZipError, ZipResult};
 
 pub const LOCAL_FILE_HEADER_SIGNATURE: u32 = 0x04034b50;
 pub const CENTRAL_DIRECTORY_HEADER_SIGNATURE: u32 = 0x02014b50;
-const CENTRAL_DIRECTORY_END_SIGNATURE: u32 = 0x06054b50;
+pub const CENTRAL_DIRECTORY_END_SIGNATURE: u32 = 0x06054b50;
 pub const ZIP64_CENTRAL_DIRECTORY_END_SIGNATURE: u32 = 0x06064b50;
-const ZIP64_CENTRAL_DIRECTORY_END_LOCATOR_SIGNATURE: u32 = 0x07064b50;
+pub const ZIP64_CENTRAL_DIRECTORY_END_LOCATOR_SIGNATURE: u32 = 0x07064b50;
+pub const DATA_DESCRIPTOR_SIGNATURE: u32 = 0x08074b50;
+// Starts the first segment of a split archive.
+pub const SPLIT_ARCHIVE_SIGNATURE: u32 = 0x08074b50;
+// Replaces it when splitting was asked for but the archive fit in one segment.
+pub const SINGLE_SEGMENT_SIGNATURE: u32 = 0x30304b50;
+
+pub const CENTRAL_DIRECTORY_HEADER_SIZE: usize = 46;
+pub const CENTRAL_DIRECTORY_END_SIZE: u64 = 22;
+pub const ZIP64_CENTRAL_DIRECTORY_END_SIZE: u64 = 56;
+pub const ZIP64_CENTRAL_DIRECTORY_END_LOCATOR_SIZE: u64 = 20;
+const ZIP64_CENTRAL_DIRECTORY_END_SEARCH_LEN: u64 = 64 * 1024;
 
 pub const ZIP64_BYTES_THR: u64 = u32::MAX as u64;
 pub const ZIP64_ENTRY_THR: usize = u16::MAX as usize;
 
+pub const SYSTEM_DOS: u16 = 0;
+pub const SYSTEM_UNIX: u16 = 3;
+pub const SYSTEM_OSX: u16 = 19;
+
+pub const S_IFMT: u32 = 0o170000;
+pub const S_IFREG: u32 = 0o100000;
+pub const S_IFDIR: u32 = 0o040000;
+pub const S_IFLNK: u32 = 0o120000;
+
+// Result of parsing a record from a byte slice: either the record and the number of bytes it
+// occupies, or the total number of bytes the slice has to contain for the record to be parsed.
+pub enum Parsed<T> {
+    Complete(T, usize),
+    Incomplete(usize),
+}
+
+impl<T> Parsed<T> {
+    pub fn complete(self) -> ZipResult<T> {
+        match self {
+            Parsed::Complete(value, _) => Ok(value),
+            Parsed::Incomplete(_) => Err(ZipError::Io(io::ErrorKind::UnexpectedEof.into())),
+        }
+    }
+}
+
 pub struct CentralDirectoryEnd {
     pub disk_number: u16,
     pub disk_with_central_directory: u16,
@@ -23,16 +59,21 @@ pub struct CentralDirectoryEnd {
 }
 
 impl CentralDirectoryEnd {
-    pub(crate) fn record_too_small(&self) -> bool {
-        self.disk_number == 0xFFFF
-            || self.disk_with_central_directory == 0xFFFF
-            || self.number_of_files_on_this_disk == 0xFFFF
-            || self.number_of_files == 0xFFFF
-            || self.central_directory_size == 0xFFFFFFFF
-            || self.central_directory_offset == 0xFFFFFFFF
+    pub fn parse<T: Read>(reader: &mut T) -> ZipResult<CentralDirectoryEnd> {
+        let mut record = vec![0; CENTRAL_DIRECTORY_END_SIZE as usize];
+        reader.read_exact(&mut record)?;
+        if let Parsed::Incomplete(size) = Self::parse_slice(&record)? {
+            record.resize(size, 0);
+            reader.read_exact(&mut record[CENTRAL_DIRECTORY_END_SIZE as usize..])?;
+        }
+        Self::parse_slice(&record)?.complete()
     }
 
-    pub fn parse<T: Read>(reader: &mut T) -> ZipResult<CentralDirectoryEnd> {
+    pub fn parse_slice(buf: &[u8]) -> ZipResult<Parsed<CentralDirectoryEnd>> {
+        if (buf.len() as u64) < CENTRAL_DIRECTORY_END_SIZE {
+            return Ok(Parsed::Incomplete(CENTRAL_DIRECTORY_END_SIZE as usize));
+        }
+        let mut reader = buf;
         let magic = reader.read_u32::<LittleEndian>()?;
         if magic != CENTRAL_DIRECTORY_END_SIGNATURE {
             return Err(ZipError::InvalidArchive("Invalid digital signature header"));
@@ -44,49 +85,81 @@ impl CentralDirectoryEnd {
         let central_directory_size = reader.read_u32::<LittleEndian>()?;
         let central_directory_offset = reader.read_u32::<LittleEndian>()?;
         let zip_file_comment_length = reader.read_u16::<LittleEndian>()? as usize;
-        let mut zip_file_comment = vec![0; zip_file_comment_length];
-        reader.read_exact(&mut zip_file_comment)?;
+        let size = CENTRAL_DIRECTORY_END_SIZE as usize + zip_file_comment_length;
+        if buf.len() < size {
+            return Ok(Parsed::Incomplete(size));
+        }
+        let zip_file_comment = reader[..zip_file_comment_length].to_vec();
+
+        Ok(Parsed::Complete(
+            CentralDirectoryEnd {
+                disk_number,
+                disk_with_central_directory,
+                number_of_files_on_this_disk,
+                number_of_files,
+                central_directory_size,
+                central_directory_offset,
+                zip_file_comment,
+            },
+            size,
+        ))
+    }
+
+    pub fn write<T: Write>(&self, writer: &mut T) -> ZipResult<()> {
+        writer.write_u32::<LittleEndian>(CENTRAL_DIRECTORY_END_SIGNATURE)?;
+        writer.write_u16::<LittleEndian>(self.disk_number)?;
+        writer.write_u16::<LittleEndian>(self.disk_with_central_directory)?;
+        writer.write_u16::<LittleEndian>(self.number_of_files_on_this_disk)?;
+        writer.write_u16::<LittleEndian>(self.number_of_files)?;
+        writer.write_u32::<LittleEndian>(self.central_directory_size)?;
+        writer.write_u32::<LittleEndian>(self.central_directory_offset)?;
+        writer.write_u16::<LittleEndian>(self.zip_file_comment.len() as u16)?;
+        writer.write_all(&self.zip_file_comment)?;
+        Ok(())
+    }
 
-        Ok(CentralDirectoryEnd {
-            disk_number,
-            disk_with_central_directory,
-            number_of_files_on_this_disk,
-            number_of_files,
-            central_directory_size,
-            central_directory_offset,
-            zip_file_comment,
-        })
+    // Number of bytes at the end of a file that can contain the end of central directory record,
+    // along with the zip64 locator in front of it when the comment has the maximum length.
+    pub fn tail_len(file_length: u64) -> u64 {
+        file_length.min(ZIP64_CENTRAL_DIRECTORY_END_LOCATOR_SIZE + CENTRAL_DIRECTORY_END_SIZE + u16::MAX as u64)
     }
 
-    pub fn find_and_parse<T: Read + io::Seek>(
+    pub fn find_and_parse<T: Read + Seek>(
         reader: &mut T,
     ) -> ZipResult<(CentralDirectoryEnd, u64)> {
-        const HEADER_SIZE: u64 = 22;
-        const BYTES_BETWEEN_MAGIC_AND_COMMENT_SIZE: u64 = HEADER_SIZE - 6;
         let file_length = reader.seek(io::SeekFrom::End(0))?;
+        let tail_offset = file_length - Self::tail_len(file_length);
+        reader.seek(io::SeekFrom::Start(tail_offset))?;
+        let mut tail = Vec::new();
+        reader.read_to_end(&mut tail)?;
+        Self::find_and_parse_tail(&tail, tail_offset)
+    }
 
-        let search_upper_bound = file_length.saturating_sub(HEADER_SIZE + ::std::u16::MAX as u64);
-
-        if file_length < HEADER_SIZE {
+    // Scans the tail of a file backwards for the record. A record whose comment ends exactly at the
+    // end of the file wins, so a signature inside a comment is not mistaken for it; failing that the
+    // record closest to the end is used, which tolerates junk appended to the archive.
+    pub fn find_and_parse_tail(tail: &[u8], tail_offset: u64) -> ZipResult<(CentralDirectoryEnd, u64)> {
+        if (tail.len() as u64) < CENTRAL_DIRECTORY_END_SIZE {
             return Err(ZipError::InvalidArchive("Invalid zip header"));
         }
 
-        let mut pos = file_length - HEADER_SIZE;
-        while pos >= search_upper_bound {
-            reader.seek(io::SeekFrom::Start(pos))?;
-            if reader.read_u32::<LittleEndian>()? == CENTRAL_DIRECTORY_END_SIGNATURE {
-                reader.seek(io::SeekFrom::Current(
-                    BYTES_BETWEEN_MAGIC_AND_COMMENT_SIZE as i64,
-                ))?;
-                let cde_start_pos = reader.seek(io::SeekFrom::Start(pos))?;
-                return CentralDirectoryEnd::parse(reader).map(|cde| (cde, cde_start_pos));
+        let signature = CENTRAL_DIRECTORY_END_SIGNATURE.to_le_bytes();
+        let mut fallback = None;
+        let mut end = tail.len() - CENTRAL_DIRECTORY_END_SIZE as usize + signature.len();
+        while let Some(pos) = tail[..end].windows(signature.len()).rposition(|window| window == signature) {
+            match Self::parse_slice(&tail[pos..])? {
+                Parsed::Complete(cde, size) if pos + size == tail.len() => {
+                    return Ok((cde, tail_offset + pos as u64));
+                }
+                Parsed::Complete(cde, _) => {
+                    fallback.get_or_insert((cde, tail_offset + pos as u64));
+                }
+                // The comment would run past the end of the file, so this is not the record.
+                Parsed::Incomplete(_) => {}
             }
-            pos = match pos.checked_sub(1) {
-                Some(p) => p,
-                None => break,
-            };
+            end = pos + signature.len() - 1;
         }
-        Err(ZipError::InvalidArchive(
+        fallback.ok_or(ZipError::InvalidArchive(
             "Could not find central directory end",
         ))
     }
@@ -100,6 +173,17 @@ pub struct Zip64CentralDirectoryEndLocator {
 
 impl Zip64CentralDirectoryEndLocator {
     pub fn parse<T: Read>(reader: &mut T) -> ZipResult<Zip64CentralDirectoryEndLocator> {
+        let mut record = [0; ZIP64_CENTRAL_DIRECTORY_END_LOCATOR_SIZE as usize];
+        reader.read_exact(&mut record)?;
+        Self::parse_slice(&record)?.complete()
+    }
+
+    pub fn parse_slice(buf: &[u8]) -> ZipResult<Parsed<Zip64CentralDirectoryEndLocator>> {
+        let size = ZIP64_CENTRAL_DIRECTORY_END_LOCATOR_SIZE as usize;
+        if buf.len() < size {
+            return Ok(Parsed::Incomplete(size));
+        }
+        let mut reader = buf;
         let magic = reader.read_u32::<LittleEndian>()?;
         if magic != ZIP64_CENTRAL_DIRECTORY_END_LOCATOR_SIGNATURE {
             return Err(ZipError::InvalidArchive(
@@ -110,11 +194,22 @@ impl Zip64CentralDirectoryEndLocator {
         let end_of_central_directory_offset = reader.read_u64::<LittleEndian>()?;
         let number_of_disks = reader.read_u32::<LittleEndian>()?;
 
-        Ok(Zip64CentralDirectoryEndLocator {
-            disk_with_central_directory,
-            end_of_central_directory_offset,
-            number_of_disks,
-        })
+        Ok(Parsed::Complete(
+            Zip64CentralDirectoryEndLocator {
+                disk_with_central_directory,
+                end_of_central_directory_offset,
+                number_of_disks,
+            },
+            size,
+        ))
+    }
+
+    pub fn write<T: Write>(&self, writer: &mut T) -> ZipResult<()> {
+        writer.write_u32::<LittleEndian>(ZIP64_CENTRAL_DIRECTORY_END_LOCATOR_SIGNATURE)?;
+        writer.write_u32::<LittleEndian>(self.disk_with_central_directory)?;
+        writer.write_u64::<LittleEndian>(self.end_of_central_directory_offset)?;
+        writer.write_u32::<LittleEndian>(self.number_of_disks)?;
+        Ok(())
     }
 }
 
@@ -130,58 +225,127 @@ pub struct Zip64CentralDirectoryEnd {
 }
 
 impl Zip64CentralDirectoryEnd {
-    pub fn find_and_parse<T: Read + io::Seek>(
+    pub fn parse<T: Read>(reader: &mut T) -> ZipResult<Zip64CentralDirectoryEnd> {
+        let mut record = [0; ZIP64_CENTRAL_DIRECTORY_END_SIZE as usize];
+        reader.read_exact(&mut record)?;
+        Self::parse_slice(&record)?.complete()
+    }
+
+    pub fn parse_slice(buf: &[u8]) -> ZipResult<Parsed<Zip64CentralDirectoryEnd>> {
+        let size = ZIP64_CENTRAL_DIRECTORY_END_SIZE as usize;
+        if buf.len() < size {
+            return Ok(Parsed::Incomplete(size));
+        }
+        let mut reader = buf;
+        let magic = reader.read_u32::<LittleEndian>()?;
+        if magic != ZIP64_CENTRAL_DIRECTORY_END_SIGNATURE {
+            return Err(ZipError::InvalidArchive(
+                "Invalid zip64 central directory end digital signature header",
+            ));
+        }
+        let _record_size = reader.read_u64::<LittleEndian>()?;
+
+        let version_made_by = reader.read_u16::<LittleEndian>()?;
+        let version_needed_to_extract = reader.read_u16::<LittleEndian>()?;
+        let disk_number = reader.read_u32::<LittleEndian>()?;
+        let disk_with_central_directory = reader.read_u32::<LittleEndian>()?;
+        let number_of_files_on_this_disk = reader.read_u64::<LittleEndian>()?;
+        let number_of_files = reader.read_u64::<LittleEndian>()?;
+        let central_directory_size = reader.read_u64::<LittleEndian>()?;
+        let central_directory_offset = reader.read_u64::<LittleEndian>()?;
+
+        Ok(Parsed::Complete(
+            Zip64CentralDirectoryEnd {
+                version_made_by,
+                version_needed_to_extract,
+                disk_number,
+                disk_with_central_directory,
+                number_of_files_on_this_disk,
+                number_of_files,
+                central_directory_size,
+                central_directory_offset,
+            },
+            size,
+        ))
+    }
+
+    pub fn write<T: Write>(&self, writer: &mut T) -> ZipResult<()> {
+        writer.write_u32::<LittleEndian>(ZIP64_CENTRAL_DIRECTORY_END_SIGNATURE)?;
+        // Size of the rest of the record.
+        writer.write_u64::<LittleEndian>(ZIP64_CENTRAL_DIRECTORY_END_SIZE - 12)?;
+        writer.write_u16::<LittleEndian>(self.version_made_by)?;
+        writer.write_u16::<LittleEndian>(self.version_needed_to_extract)?;
+        writer.write_u32::<LittleEndian>(self.disk_number)?;
+        writer.write_u32::<LittleEndian>(self.disk_with_central_directory)?;
+        writer.write_u64::<LittleEndian>(self.number_of_files_on_this_disk)?;
+        writer.write_u64::<LittleEndian>(self.number_of_files)?;
+        writer.write_u64::<LittleEndian>(self.central_directory_size)?;
+        writer.write_u64::<LittleEndian>(self.central_directory_offset)?;
+        Ok(())
+    }
+
+    // Bytes before the zip64 locator that are searched for the record, which ends right where the
+    // locator starts.
+    pub fn search_start(nominal_offset: u64, locator_offset: u64) -> u64 {
+        locator_offset.saturating_sub(ZIP64_CENTRAL_DIRECTORY_END_SEARCH_LEN).max(nominal_offset)
+    }
+
+    pub fn find_and_parse<T: Read + Seek>(
         reader: &mut T,
         nominal_offset: u64,
-        search_upper_bound: u64,
+        locator_offset: u64,
     ) -> ZipResult<(Zip64CentralDirectoryEnd, u64)> {
-        let mut pos = nominal_offset;
-
-        while pos <= search_upper_bound {
-            reader.seek(io::SeekFrom::Start(pos))?;
-
-            if reader.read_u32::<LittleEndian>()? == ZIP64_CENTRAL_DIRECTORY_END_SIGNATURE {
-                let archive_offset = pos - nominal_offset;
-
-                let _record_size = reader.read_u64::<LittleEndian>()?;
-
-                let version_made_by = reader.read_u16::<LittleEndian>()?;
-                let version_needed_to_extract = reader.read_u16::<LittleEndian>()?;
-                let disk_number = reader.read_u32::<LittleEndian>()?;
-                let disk_with_central_directory = reader.read_u32::<LittleEndian>()?;
-                let number_of_files_on_this_disk = reader.read_u64::<LittleEndian>()?;
-                let number_of_files = reader.read_u64::<LittleEndian>()?;
-                let central_directory_size = reader.read_u64::<LittleEndian>()?;
-                let central_directory_offset = reader.read_u64::<LittleEndian>()?;
-
-                return Ok((
-                    Zip64CentralDirectoryEnd {
-                        version_made_by,
-                        version_needed_to_extract,
-                        disk_number,
-                        disk_with_central_directory,
-                        number_of_files_on_this_disk,
-                        number_of_files,
-                        central_directory_size,
-                        central_directory_offset,
-                    },
-                    archive_offset,
-                ));
-            }
+        let search_start = Self::search_start(nominal_offset, locator_offset);
+        reader.seek(io::SeekFrom::Start(search_start))?;
+        let mut region = Vec::new();
+        reader
+            .take(locator_offset.saturating_sub(search_start))
+            .read_to_end(&mut region)?;
+        let (footer, record_offset) = Self::find_and_parse_slice(&region, search_start)?;
+        Ok((footer, record_offset - nominal_offset))
+    }
+
+    // Searches `region`, which starts at `region_offset` and ends where the zip64 locator starts,
+    // backwards for the record and returns it with its offset. A record whose size field makes it
+    // end exactly at the locator wins over any other signature match.
+    pub fn find_and_parse_slice(region: &[u8], region_offset: u64) -> ZipResult<(Zip64CentralDirectoryEnd, u64)> {
+        let signature = ZIP64_CENTRAL_DIRECTORY_END_SIGNATURE.to_le_bytes();
+        let Some(last) = region.len().checked_sub(ZIP64_CENTRAL_DIRECTORY_END_SIZE as usize) else {
+            return Err(ZipError::InvalidArchive("Could not find ZIP64 central directory end"));
+        };
 
-            pos += 1;
+        let mut fallback = None;
+        let mut end = last + signature.len();
+        while let Some(pos) = region[..end].windows(signature.len()).rposition(|window| window == signature) {
+            let footer = Self::parse_slice(&region[pos..])?.complete()?;
+            let record_size = (&region[pos + 4..]).read_u64::<LittleEndian>()?;
+            if (pos as u64).saturating_add(12).saturating_add(record_size) == region.len() as u64 {
+                return Ok((footer, region_offset + pos as u64));
+            }
+            fallback.get_or_insert((footer, region_offset + pos as u64));
+            end = pos + signature.len() - 1;
         }
 
-        Err(ZipError::InvalidArchive(
+        fallback.ok_or(ZipError::InvalidArchive(
             "Could not find ZIP64 central directory end",
         ))
     }
 }
 
-#[derive(PartialEq)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum CompressionMethod {
     Stored,
     Deflate,
+    #[cfg(feature = "deflate64")]
+    Deflate64,
+    #[cfg(feature = "bzip2")]
+    Bzip2,
+    #[cfg(feature = "lzma")]
+    Lzma,
+    #[cfg(feature = "zstd")]
+    Zstd,
+    #[cfg(feature = "xz")]
+    Xz,
     Unsupported(u16),
 }
 
@@ -190,6 +354,16 @@ impl CompressionMethod {
         match val {
             0 => CompressionMethod::Stored,
             8 => CompressionMethod::Deflate,
+            #[cfg(feature = "deflate64")]
+            9 => CompressionMethod::Deflate64,
+            #[cfg(feature = "bzip2")]
+            12 => CompressionMethod::Bzip2,
+            #[cfg(feature = "lzma")]
+            14 => CompressionMethod::Lzma,
+            #[cfg(feature = "zstd")]
+            93 => CompressionMethod::Zstd,
+            #[cfg(feature = "xz")]
+            95 => CompressionMethod::Xz,
             v => CompressionMethod::Unsupported(v),
         }
     }
@@ -198,6 +372,16 @@ impl CompressionMethod {
         match self {
             CompressionMethod::Stored => 0,
             CompressionMethod::Deflate => 8,
+            #[cfg(feature = "deflate64")]
+            CompressionMethod::Deflate64 => 9,
+            #[cfg(feature = "bzip2")]
+            CompressionMethod::Bzip2 => 12,
+            #[cfg(feature = "lzma")]
+            CompressionMethod::Lzma => 14,
+            #[cfg(feature = "zstd")]
+            CompressionMethod::Zstd => 93,
+            #[cfg(feature = "xz")]
+            CompressionMethod::Xz => 95,
             CompressionMethod::Unsupported(v) => v,
         }
     }
@@ -206,7 +390,7 @@ impl CompressionMethod {
 impl fmt::Display for CompressionMethod {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         // Just duplicate what the Debug format looks like, i.e, the enum key:
-        write!(f, "{}", self)
+        write!(f, "{:?}", self)
     }
 }
 
@@ -0,0 +1,76 @@
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use crate::zip::result::{ZipError, ZipResult};
+use crate::zip::storage::{SplitStorage, Storage};
+use crate::zip::zip_archive::{ZipArchive, ZipArchiveOptions};
+
+impl ZipArchive {
+    // Opens an archive that may be split over several files, found next to `path`:
+    // - `archive.zip.001`, `archive.zip.002`, ... are cut at arbitrary bytes and simply joined.
+    // - `archive.z01`, `archive.z02`, ..., `archive.zip` are the disks of a split archive, `path`
+    //   names the last one.
+    // Anything else is opened as a single file.
+    pub fn open_split(path: impl AsRef<Path>, options: ZipArchiveOptions) -> ZipResult<ZipArchive> {
+        let path = path.as_ref();
+        if let Some(parts) = byte_split_parts(path) {
+            let storage = SplitStorage::new(open_parts(&parts)?)?;
+            return Self::from_storage(Arc::new(storage), options);
+        }
+
+        let mut disks = disk_parts(path);
+        if disks.is_empty() {
+            return Self::with_options(path, options);
+        }
+        disks.push(path.to_path_buf());
+        Self::from_disks(open_parts(&disks)?, options)
+    }
+
+    // Opens the disks of a split archive, in order. Offsets recorded in the archive are resolved
+    // against the disk they name, a missing disk is reported as `MissingDisk`.
+    pub fn from_disks(disks: Vec<Arc<dyn Storage>>, options: ZipArchiveOptions) -> ZipResult<ZipArchive> {
+        if disks.is_empty() {
+            return Err(ZipError::InvalidArchive("Split archive has no disks"));
+        }
+        let storage = SplitStorage::new(disks)?;
+        let disk_offsets = storage.part_offsets().to_vec();
+        Self::open_storage(Arc::new(storage), disk_offsets, options)
+    }
+}
+
+fn open_parts(paths: &[PathBuf]) -> ZipResult<Vec<Arc<dyn Storage>>> {
+    let mut parts: Vec<Arc<dyn Storage>> = Vec::with_capacity(paths.len());
+    for path in paths {
+        parts.push(Arc::new(File::open(path)?));
+    }
+    Ok(parts)
+}
+
+// `archive.zip.001` and every following part that exists, whichever part `path` names.
+fn byte_split_parts(path: &Path) -> Option<Vec<PathBuf>> {
+    let extension = path.extension()?.to_str()?;
+    if extension.len() < 3 || !extension.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let width = extension.len();
+    let parts: Vec<PathBuf> = (1..)
+        .map(|index| path.with_extension(format!("{:0width$}", index, width = width)))
+        .take_while(|part| part.is_file())
+        .collect();
+    (!parts.is_empty()).then_some(parts)
+}
+
+// `archive.z01`, `archive.z02`, ... in front of the last disk `archive.zip`.
+fn disk_parts(path: &Path) -> Vec<PathBuf> {
+    let is_zip = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("zip"));
+    if !is_zip {
+        return Vec::new();
+    }
+    (1..)
+        .map(|index| path.with_extension(format!("z{:02}", index)))
+        .take_while(|part| part.is_file())
+        .collect()
+}
@@ -0,0 +1,146 @@
+use std::fs::File;
+use std::io;
+use std::sync::Arc;
+use crate::zip::positional;
+
+// Random access to the bytes of an archive. Reads never share a cursor, so one storage can serve
+// any number of entry readers at once.
+pub trait Storage: Send + Sync {
+    fn len(&self) -> io::Result<u64>;
+
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize>;
+
+    fn is_empty(&self) -> io::Result<bool> {
+        Ok(self.len()? == 0)
+    }
+
+    fn read_exact_at(&self, mut buf: &mut [u8], mut offset: u64) -> io::Result<()> {
+        while !buf.is_empty() {
+            match self.read_at(buf, offset) {
+                Ok(0) => return Err(io::Error::from(io::ErrorKind::UnexpectedEof)),
+                Ok(size) => {
+                    buf = &mut buf[size..];
+                    offset += size as u64;
+                }
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Storage for File {
+    fn len(&self) -> io::Result<u64> {
+        Ok(self.metadata()?.len())
+    }
+
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        positional::read_at(self, buf, offset)
+    }
+}
+
+impl Storage for Vec<u8> {
+    fn len(&self) -> io::Result<u64> {
+        Ok(self.as_slice().len() as u64)
+    }
+
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        let start = offset.min(self.as_slice().len() as u64) as usize;
+        let size = buf.len().min(self.as_slice().len() - start);
+        buf[..size].copy_from_slice(&self[start..start + size]);
+        Ok(size)
+    }
+}
+
+// The bytes `[offset, offset + len)` of another storage, seen as a storage of their own.
+pub struct RangeStorage {
+    inner: Arc<dyn Storage>,
+    offset: u64,
+    len: u64,
+}
+
+impl RangeStorage {
+    pub fn new(inner: Arc<dyn Storage>, offset: u64, len: u64) -> io::Result<Self> {
+        let inner_len = inner.len()?;
+        if offset.checked_add(len).is_none_or(|end| end > inner_len) {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "Range extends past the end of the storage"));
+        }
+        Ok(Self { inner, offset, len })
+    }
+}
+
+impl Storage for RangeStorage {
+    fn len(&self) -> io::Result<u64> {
+        Ok(self.len)
+    }
+
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        if offset >= self.len {
+            return Ok(0);
+        }
+        let limit = (self.len - offset).min(buf.len() as u64) as usize;
+        self.inner.read_at(&mut buf[..limit], self.offset + offset)
+    }
+}
+
+// Several storages read back to back as one, such as the parts of a split archive.
+pub struct SplitStorage {
+    parts: Vec<Arc<dyn Storage>>,
+    // Offset at which every part starts, followed by the total length.
+    offsets: Vec<u64>,
+}
+
+impl SplitStorage {
+    pub fn new(parts: Vec<Arc<dyn Storage>>) -> io::Result<Self> {
+        let mut offsets = Vec::with_capacity(parts.len() + 1);
+        let mut end = 0u64;
+        offsets.push(end);
+        for part in &parts {
+            end = end
+                .checked_add(part.len()?)
+                .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Parts are too large"))?;
+            offsets.push(end);
+        }
+        Ok(Self { parts, offsets })
+    }
+
+    pub fn part_offsets(&self) -> &[u64] {
+        &self.offsets[..self.parts.len()]
+    }
+}
+
+impl Storage for SplitStorage {
+    fn len(&self) -> io::Result<u64> {
+        Ok(self.offsets[self.parts.len()])
+    }
+
+    // Reads never cross a part boundary, callers loop for the rest.
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        let index = self.offsets.partition_point(|start| *start <= offset);
+        if index == 0 || index > self.parts.len() {
+            return Ok(0);
+        }
+        let part_offset = offset - self.offsets[index - 1];
+        let limit = (self.offsets[index] - offset).min(buf.len() as u64) as usize;
+        self.parts[index - 1].read_at(&mut buf[..limit], part_offset)
+    }
+}
+
+// Reads up to `len` bytes at `offset`, stopping early at the end of the storage. `len` often comes
+// from the archive itself, so no more is allocated than the storage holds.
+pub fn read_range(storage: &dyn Storage, offset: u64, len: u64) -> io::Result<Vec<u8>> {
+    let len = len.min(storage.len()?.saturating_sub(offset));
+    let mut buf = vec![0u8; len as usize];
+    let mut filled = 0;
+    while filled < buf.len() {
+        match storage.read_at(&mut buf[filled..], offset + filled as u64) {
+            Ok(0) => break,
+            Ok(size) => filled += size,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    buf.truncate(filled);
+    Ok(buf)
+}
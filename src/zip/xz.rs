@@ -0,0 +1,27 @@
+use std::io::{Error, ErrorKind, Read, Seek, SeekFrom};
+use xz2::read::XzDecoder;
+use crate::zip::plaintext::PlaintextReader;
+
+pub struct XzReader {
+    decoder: XzDecoder<PlaintextReader>,
+}
+
+impl XzReader {
+    pub fn new(reader: PlaintextReader) -> Self {
+        Self {
+            decoder: XzDecoder::new(reader),
+        }
+    }
+}
+
+impl Read for XzReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.decoder.read(buf)
+    }
+}
+
+impl Seek for XzReader {
+    fn seek(&mut self, _pos: SeekFrom) -> std::io::Result<u64> {
+        Err(Error::new(ErrorKind::Unsupported, "Seek is not supported for xz entries"))
+    }
+}
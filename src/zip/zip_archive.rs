@@ -1,169 +1,496 @@
 use std::collections::HashMap;
-use std::fs::{File, OpenOptions};
+use std::fs::OpenOptions;
 use std::io;
 use std::io::{Read, Seek};
 use std::path::Path;
-use std::sync::{Arc};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
 use byteorder::{LittleEndian, ReadBytesExt};
-use parking_lot::RwLock;
+use parking_lot::Mutex;
+use flate2::{Decompress, FlushDecompress, Status};
+use crate::zip::block_cache::{BlockCacheOptions, CacheStats, CachedStorage};
+use crate::zip::codec::CodecRegistry;
+use crate::zip::directory::{DirectoryParser, ParseStep};
+use crate::zip::encoding::NameEncoding;
+use crate::zip::entry_cache::EntryCache;
+use crate::zip::limits::ZipLimits;
+use crate::zip::plaintext::PlaintextReader;
 use crate::zip::result::{ZipError, ZipResult};
 use crate::zip::spec;
+use crate::zip::storage;
+use crate::zip::storage::{RangeStorage, Storage};
 use crate::zip::zip_file::*;
 
 pub struct ZipArchive {
-    pub file: Arc<RwLock<File>>,
+    pub storage: Arc<dyn Storage>,
     pub entries: HashMap<String, Arc<ZipFileData>>,
+    pub codecs: CodecRegistry,
+    pub limits: ZipLimits,
+    // Offset all offsets recorded in the archive are relative to.
+    pub archive_offset: u64,
+    pub cache: Option<Arc<CachedStorage>>,
+    pub entry_cache: Option<EntryCache>,
+    pub deflate_buffer_size: usize,
+    // Code page legacy entry names were decoded with, never `Auto`.
+    pub name_encoding: NameEncoding,
+    // As configured, nested archives pick their own encoding when it is `Auto`.
+    requested_name_encoding: NameEncoding,
+    // Entries whose name is not simply their raw bytes, or that share their name with another entry,
+    // by raw name for `by_name_bytes`.
+    raw_entries: HashMap<Vec<u8>, Arc<ZipFileData>>,
+    lossy_collisions: Vec<String>,
+    // Identifies this archive in a shared entry cache.
+    id: u64,
+    // Nested archives opened by `mount` or `by_path`, by entry name. `None` marks entries `by_path`
+    // found not to be archives.
+    mounts: Mutex<HashMap<String, Option<Arc<ZipArchive>>>>,
+}
+
+static NEXT_ARCHIVE_ID: AtomicU64 = AtomicU64::new(0);
+
+// Deflate cannot expand a byte of input into more than 1032 bytes of output.
+const MAX_DEFLATE_RATIO: u64 = 1032;
+
+#[derive(Clone, Default)]
+pub struct ZipArchiveOptions {
+    pub codecs: CodecRegistry,
+    pub limits: ZipLimits,
+    // Puts an LRU block cache between the storage and the entry readers.
+    pub block_cache: Option<BlockCacheOptions>,
+    // Keeps decompressed entries around, the same cache can be shared between archives.
+    pub entry_cache: Option<EntryCache>,
+    // Input buffer of deflate entry readers in bytes, 0 uses a 64 KiB buffer.
+    pub deflate_buffer_size: usize,
+    // Code page of entry names that are not flagged as UTF-8.
+    pub name_encoding: NameEncoding,
 }
 
 impl ZipArchive {
     pub fn new(path: impl AsRef<Path>) -> ZipResult<ZipArchive> {
-        let mut file = OpenOptions::new().read(true).open(path)?;
+        Self::with_codecs(path, CodecRegistry::new())
+    }
 
-        let (footer, cde_start_pos) = spec::CentralDirectoryEnd::find_and_parse(&mut file)?;
+    pub fn with_codecs(path: impl AsRef<Path>, codecs: CodecRegistry) -> ZipResult<ZipArchive> {
+        Self::with_options(path, ZipArchiveOptions { codecs, ..Default::default() })
+    }
 
-        if !footer.record_too_small() && footer.disk_number != footer.disk_with_central_directory {
-            return Err(ZipError::UnsupportedArchive);
-        }
+    pub fn with_options(path: impl AsRef<Path>, options: ZipArchiveOptions) -> ZipResult<ZipArchive> {
+        let file = OpenOptions::new().read(true).open(path)?;
+        Self::from_storage(Arc::new(file), options)
+    }
 
-        let (archive_offset, directory_start, number_of_files) =
-            Self::get_directory_counts(&mut file, &footer, cde_start_pos)?;
+    // Opens an archive stored in `[offset, offset + len)` of a larger file, the rest of the file is
+    // invisible to it.
+    pub fn with_range(path: impl AsRef<Path>, offset: u64, len: u64, options: ZipArchiveOptions) -> ZipResult<ZipArchive> {
+        let file = OpenOptions::new().read(true).open(path)?;
+        Self::from_storage(Arc::new(RangeStorage::new(Arc::new(file), offset, len)?), options)
+    }
+
+    pub fn from_storage(storage: Arc<dyn Storage>, options: ZipArchiveOptions) -> ZipResult<ZipArchive> {
+        Self::open_storage(storage, vec![0], options)
+    }
+
+    // `disk_offsets` holds where every disk of a split archive starts within `storage`.
+    pub(crate) fn open_storage(storage: Arc<dyn Storage>, disk_offsets: Vec<u64>, options: ZipArchiveOptions) -> ZipResult<ZipArchive> {
+        let ZipArchiveOptions { codecs, limits, block_cache, entry_cache, deflate_buffer_size, name_encoding } = options;
 
-        let file_capacity = if number_of_files > cde_start_pos as usize {
-            0
-        } else {
-            number_of_files
+        let cache = block_cache.map(|options| Arc::new(CachedStorage::new(storage.clone(), options)));
+        let storage = match &cache {
+            Some(cache) => cache.clone(),
+            None => storage,
         };
 
-        let mut entries = HashMap::with_capacity(file_capacity);
+        let file_length = storage.len()?;
+        let mut parser = DirectoryParser::with_disks(file_length, limits.clone(), disk_offsets);
+        parser.set_name_encoding(name_encoding);
+        let mut step = parser.start();
+        let entries = loop {
+            match step {
+                ParseStep::NeedData { offset, len } => step = parser.feed(&storage::read_range(&*storage, offset, len)?)?,
+                ParseStep::Done(entries) => break entries,
+            }
+        };
+        let archive_offset = parser.archive_offset().unwrap_or_default();
+        let shadowed = parser.shadowed();
+        let mut lossy_collisions: Vec<String> = shadowed.iter().map(|data| data.file_name.clone()).collect();
+        lossy_collisions.sort();
+        lossy_collisions.dedup();
+        let raw_entries = shadowed
+            .iter()
+            .chain(entries.values())
+            .filter(|data| data.file_name_raw != data.file_name.as_bytes() || lossy_collisions.contains(&data.file_name))
+            .map(|data| (data.file_name_raw.clone(), data.clone()))
+            .collect();
+
+        Ok(ZipArchive {
+            storage,
+            entries,
+            codecs,
+            limits,
+            archive_offset,
+            cache,
+            entry_cache,
+            deflate_buffer_size,
+            name_encoding: parser.name_encoding(),
+            requested_name_encoding: name_encoding,
+            raw_entries,
+            lossy_collisions,
+            id: NEXT_ARCHIVE_ID.fetch_add(1, Ordering::Relaxed),
+            mounts: Mutex::new(HashMap::new()),
+        })
+    }
+
+    // Number of bytes in front of the first entry, such as the stub of a self-extracting archive.
+    // Unlike `archive_offset` this also covers archives whose offsets were adjusted to include it.
+    // The marker a split or single segment archive starts with is part of the archive, not a prefix.
+    pub fn prefix_len(&self) -> u64 {
+        let prefix_len = self
+            .entries
+            .values()
+            .map(|data| data.header_start)
+            .min()
+            .unwrap_or(self.archive_offset);
+        let mut marker = [0u8; 4];
+        let is_marker = prefix_len == marker.len() as u64
+            && self.storage.read_exact_at(&mut marker, 0).is_ok()
+            && matches!(u32::from_le_bytes(marker), spec::SPLIT_ARCHIVE_SIGNATURE | spec::SINGLE_SEGMENT_SIGNATURE);
+        if is_marker { 0 } else { prefix_len }
+    }
+
+    pub fn prefix_reader(&self) -> impl Read + Seek + Send {
+        PlaintextReader::new(self.storage.clone(), 0, self.prefix_len())
+    }
+
+    pub fn cache_stats(&self) -> Option<CacheStats> {
+        self.cache.as_ref().map(|cache| cache.stats())
+    }
+
+    pub fn file_exist(&self, name: &str) -> bool {
+        self.entries.contains_key(name)
+    }
 
-        if file.seek(io::SeekFrom::Start(directory_start)).is_err() {
-            return Err(ZipError::InvalidArchive(
-                "Could not seek to start of central directory",
-            ));
+    pub fn is_supported(&self, data: &ZipFileData) -> bool {
+        match data.compression_method {
+            spec::CompressionMethod::Unsupported(method) => self.codecs.contains(method),
+            _ => true,
         }
+    }
 
-        for _ in 0..number_of_files {
-            match central_header_to_zip_file(&mut file, archive_offset) {
-                Ok(zip_fil_data) => {
-                    entries.insert(zip_fil_data.file_name.clone(), Arc::new(zip_fil_data));
-                }
-                Err(e) => {
-                    match e {
-                        ZipError::UnsupportedCompressionMethod(method) => continue,
-                        _ => return Err(e),
-                    }
-                }
-            }
+    pub fn unsupported_entries(&self) -> impl Iterator<Item = &Arc<ZipFileData>> {
+        self.entries.values().filter(|data| !self.is_supported(data))
+    }
+
+    pub fn by_name(&self, name: &str) -> ZipResult<ZipFile> {
+        let data = self
+            .entries
+            .get(name)
+            .ok_or(ZipError::FileNotFound)?;
+
+        if let Some(cache) = self.entry_cache.as_ref().filter(|cache| cache.fits(data.uncompressed_size)) {
+            let contents = self.read_through_cache(cache, data)?;
+            return Ok(ZipFile::new(ZipFileReader::Cached(io::Cursor::new(contents)), data.clone(), None));
         }
 
-        let file = Arc::new(RwLock::new(file));
+        self.open(data)
+    }
 
-        Ok(ZipArchive { file, entries })
+    // Looks an entry up by its name as stored in the archive, which is not valid UTF-8 or was decoded
+    // from a legacy code page for some entries.
+    pub fn by_name_bytes(&self, name: &[u8]) -> ZipResult<ZipFile> {
+        let data = self.data_for_bytes(name).ok_or(ZipError::FileNotFound)?;
+        match self.entries.get(&data.file_name) {
+            Some(indexed) if Arc::ptr_eq(indexed, data) => self.by_name(&data.file_name),
+            // Shadowed entries bypass the entry cache, which knows them by their name only.
+            _ => self.open(data),
+        }
     }
 
-    fn get_directory_counts<T: Read + io::Seek>(
-        reader: &mut T,
-        footer: &spec::CentralDirectoryEnd,
-        cde_start_pos: u64,
-    ) -> ZipResult<(u64, u64, usize)> {
-        let zip64locator = if reader
-            .seek(io::SeekFrom::End(
-                -(20 + 22 + footer.zip_file_comment.len() as i64),
-            ))
-            .is_ok()
-        {
-            match spec::Zip64CentralDirectoryEndLocator::parse(reader) {
-                Ok(loc) => Some(loc),
-                Err(ZipError::InvalidArchive(_)) => {
-                    None
-                }
-                Err(e) => {
-                    return Err(e);
-                }
-            }
-        } else {
-            None
-        };
+    // Names that several entries with different raw names decode to. `by_name` returns the last of
+    // them, `by_name_bytes` reaches each one.
+    pub fn lossy_collisions(&self) -> &[String] {
+        &self.lossy_collisions
+    }
+
+    // Returns the whole decompressed entry, served from the entry cache when one is configured.
+    pub fn read_shared(&self, name: &str) -> ZipResult<Arc<[u8]>> {
+        let data = self
+            .entries
+            .get(name)
+            .ok_or(ZipError::FileNotFound)?;
 
-        match zip64locator {
+        match self.entry_cache.as_ref().filter(|cache| cache.fits(data.uncompressed_size)) {
+            Some(cache) => self.read_through_cache(cache, data),
             None => {
-                let archive_offset = cde_start_pos
-                    .checked_sub(footer.central_directory_size as u64)
-                    .and_then(|x| x.checked_sub(footer.central_directory_offset as u64))
-                    .ok_or(ZipError::InvalidArchive(
-                        "Invalid central directory size or offset",
-                    ))?;
-
-                let directory_start = footer.central_directory_offset as u64 + archive_offset;
-                let number_of_files = footer.number_of_files_on_this_disk as usize;
-                Ok((archive_offset, directory_start, number_of_files))
+                let mut contents = Vec::new();
+                self.open(data)?.read_to_end(&mut contents)?;
+                Ok(contents.into())
             }
-            Some(locator64) => {
-                if !footer.record_too_small()
-                    && footer.disk_number as u32 != locator64.disk_with_central_directory
-                {
-                    return Err(ZipError::UnsupportedArchive);
-                }
+        }
+    }
 
-                let search_upper_bound = cde_start_pos
-                    .checked_sub(60) // minimum size of Zip64CentralDirectoryEnd + Zip64CentralDirectoryEndLocator
-                    .ok_or(ZipError::InvalidArchive(
-                        "File cannot contain ZIP64 central directory end",
-                    ))?;
-                let (footer, archive_offset) = spec::Zip64CentralDirectoryEnd::find_and_parse(
-                    reader,
-                    locator64.end_of_central_directory_offset,
-                    search_upper_bound,
-                )?;
-
-                if footer.disk_number != footer.disk_with_central_directory {
-                    return Err(ZipError::UnsupportedArchive);
-                }
+    // Opens the archive stored in entry `name` with the options of this archive. A stored entry is
+    // read in place, any other entry is decompressed into memory first.
+    pub fn open_nested(&self, name: &str) -> ZipResult<ZipArchive> {
+        let data = self
+            .entries
+            .get(name)
+            .ok_or(ZipError::FileNotFound)?;
 
-                let directory_start = footer
-                    .central_directory_offset
-                    .checked_add(archive_offset)
-                    .ok_or({
-                        ZipError::InvalidArchive("Invalid central directory size or offset")
-                    })?;
-
-                Ok((
-                    archive_offset,
-                    directory_start,
-                    footer.number_of_files as usize,
-                ))
+        let storage: Arc<dyn Storage> = match data.compression_method {
+            spec::CompressionMethod::Stored if !self.codecs.contains(data.compression_method.to_u16()) => {
+                let data_start = self.find_entry_data(data)?;
+                Arc::new(RangeStorage::new(self.storage.clone(), data_start, data.compressed_size)?)
             }
+            _ => Arc::new(self.read_to_vec(name)?),
+        };
+
+        let options = ZipArchiveOptions {
+            codecs: self.codecs.clone(),
+            limits: self.limits.clone(),
+            // Reads of a nested archive already go through the cache of this one, if any.
+            block_cache: None,
+            entry_cache: self.entry_cache.clone(),
+            deflate_buffer_size: self.deflate_buffer_size,
+            name_encoding: self.requested_name_encoding,
+        };
+        Self::from_storage(storage, options)
+    }
+
+    // Like `open_nested`, but keeps the nested archive open for later calls.
+    pub fn mount(&self, name: &str) -> ZipResult<Arc<ZipArchive>> {
+        if let Some(Some(archive)) = self.mounts.lock().get(name) {
+            return Ok(archive.clone());
         }
+        let archive = Arc::new(self.open_nested(name)?);
+        let mut mounts = self.mounts.lock();
+        let mounted = mounts.entry(name.to_string()).or_insert(None);
+        Ok(mounted.get_or_insert(archive).clone())
+    }
+
+    // Closes the nested archive mounted from entry `name`. Handles returned earlier stay usable.
+    // Returns whether it was mounted.
+    pub fn unmount(&self, name: &str) -> bool {
+        self.mounts.lock().remove(name).flatten().is_some()
     }
 
-    pub fn file_exist(&mut self, name: &str) -> bool {
-        return self
+    // Opens a file by a path that may lead through nested archives, such as
+    // `inner.zip/assets/config.json`. Entries on the way that hold archives are mounted.
+    pub fn by_path(&self, path: &str) -> ZipResult<ZipFile> {
+        if self.entries.contains_key(path) {
+            return self.by_name(path);
+        }
+        for (index, _) in path.match_indices('/') {
+            let (name, rest) = (&path[..index], &path[index + 1..]);
+            if let Some(archive) = self.mount_for_path(name)? {
+                return archive.by_path(rest);
+            }
+        }
+        Err(ZipError::FileNotFound)
+    }
+
+    // The archive in entry `name` if it holds one, remembering entries that do not.
+    fn mount_for_path(&self, name: &str) -> ZipResult<Option<Arc<ZipArchive>>> {
+        let Some(data) = self.entries.get(name).filter(|data| !data.is_dir()) else {
+            return Ok(None);
+        };
+        if let Some(mounted) = self.mounts.lock().get(name) {
+            return Ok(mounted.clone());
+        }
+
+        let archive = match self.starts_like_archive(data)? {
+            true => match self.open_nested(name) {
+                Ok(archive) => Some(Arc::new(archive)),
+                Err(ZipError::InvalidArchive(_) | ZipError::UnsupportedArchive | ZipError::MissingDisk(_)) => None,
+                Err(e) => return Err(e),
+            },
+            false => None,
+        };
+        Ok(self.mounts.lock().entry(name.to_string()).or_insert(archive).clone())
+    }
+
+    // Whether the entry begins with a local header, an end of central directory record for an empty
+    // archive, or the marker of a single segment split archive. Only these first bytes are
+    // decompressed.
+    fn starts_like_archive(&self, data: &Arc<ZipFileData>) -> ZipResult<bool> {
+        if !self.is_supported(data) {
+            return Ok(false);
+        }
+        let mut magic = Vec::with_capacity(4);
+        self.open(data)?.take(4).read_to_end(&mut magic)?;
+        let Ok(magic) = <[u8; 4]>::try_from(magic) else {
+            return Ok(false);
+        };
+        Ok(matches!(
+            u32::from_le_bytes(magic),
+            spec::LOCAL_FILE_HEADER_SIGNATURE | spec::CENTRAL_DIRECTORY_END_SIGNATURE | spec::SINGLE_SEGMENT_SIGNATURE
+        ))
+    }
+
+    // Reads a whole entry into a buffer allocated from its declared size, when that size can be
+    // validated against the compressed data; other entries are read into a growing buffer.
+    pub fn read_to_vec(&self, name: &str) -> ZipResult<Vec<u8>> {
+        let data = self
             .entries
             .get(name)
-            .is_some();
+            .ok_or(ZipError::FileNotFound)?;
+
+        if let Some(cache) = self.entry_cache.as_ref().filter(|cache| cache.fits(data.uncompressed_size)) {
+            return Ok(self.read_through_cache(cache, data)?.to_vec());
+        }
+
+        match self.preallocation_size(data)? {
+            Some(size) => {
+                let mut buf = vec![0u8; size];
+                self.read_entry_into(data, &mut buf)?;
+                Ok(buf)
+            }
+            None => {
+                let mut buf = Vec::new();
+                self.open(data)?.read_to_end(&mut buf)?;
+                Ok(buf)
+            }
+        }
     }
 
-    pub fn by_name(&mut self, name: &str) -> ZipResult<ZipFile> {
+    pub fn read_to_string(&self, name: &str) -> ZipResult<String> {
+        String::from_utf8(self.read_to_vec(name)?)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e).into())
+    }
+
+    // Decompresses a whole entry into the start of `buf` and returns its size. The entry has to
+    // decompress to exactly its declared size.
+    pub fn read_into(&self, name: &str, buf: &mut [u8]) -> ZipResult<usize> {
         let data = self
             .entries
             .get(name)
             .ok_or(ZipError::FileNotFound)?;
 
-        let position = {
-            let mut file = &*self.file.write();
-            file.seek(io::SeekFrom::Start(data.header_start))?;
-            let signature = file.read_u32::<LittleEndian>()?;
-            if signature != spec::LOCAL_FILE_HEADER_SIGNATURE {
-                return Err(ZipError::InvalidArchive("Invalid local file header"));
-            }
+        let size = usize::try_from(data.uncompressed_size)
+            .ok()
+            .filter(|size| *size <= buf.len())
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Buffer is smaller than the entry"))?;
+        let buf = &mut buf[..size];
+
+        if let Some(cache) = self.entry_cache.as_ref().filter(|cache| cache.fits(data.uncompressed_size)) {
+            let contents = self.read_through_cache(cache, data)?;
+            check_entry_size(data, contents.len())?;
+            buf.copy_from_slice(&contents);
+            return Ok(size);
+        }
+
+        self.read_entry_into(data, buf)?;
+        Ok(size)
+    }
 
-            file.seek(io::SeekFrom::Current(0))?
+    fn preallocation_size(&self, data: &ZipFileData) -> ZipResult<Option<usize>> {
+        if self.codecs.contains(data.compression_method.to_u16())
+            || data.header_start.saturating_add(data.compressed_size) > self.storage.len()?
+        {
+            return Ok(None);
+        }
+        let plausible = match data.compression_method {
+            spec::CompressionMethod::Stored => data.uncompressed_size == data.compressed_size,
+            spec::CompressionMethod::Deflate => {
+                data.uncompressed_size <= data.compressed_size.saturating_mul(MAX_DEFLATE_RATIO)
+            }
+            _ => false,
         };
+        Ok(plausible.then(|| usize::try_from(data.uncompressed_size).ok()).flatten())
+    }
+
+    // Stored and deflated entries are read with a single read of their compressed bytes and a
+    // single inflate call into `buf`, which must be exactly the declared size.
+    fn read_entry_into(&self, data: &Arc<ZipFileData>, buf: &mut [u8]) -> ZipResult<()> {
+        let builtin = !self.codecs.contains(data.compression_method.to_u16());
+        match data.compression_method {
+            spec::CompressionMethod::Stored if builtin => {
+                let data_start = self.find_entry_data(data)?;
+                check_entry_size(data, data.compressed_size as usize)?;
+                self.storage.read_exact_at(buf, data_start)?;
+            }
+            spec::CompressionMethod::Deflate if builtin => {
+                let data_start = self.find_entry_data(data)?;
+                let mut input = vec![0u8; data.compressed_size as usize];
+                self.storage.read_exact_at(&mut input, data_start)?;
+
+                let mut decompress = Decompress::new(false);
+                let status = decompress
+                    .decompress(&input, buf, FlushDecompress::Finish)
+                    .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "corrupt deflate stream"))?;
+                if status != Status::StreamEnd && decompress.total_out() as usize == buf.len() {
+                    return Err(ZipError::EntrySizeExceeded(data.file_name.clone()));
+                }
+                check_entry_size(data, decompress.total_out() as usize)?;
+            }
+            _ => {
+                let mut file = self.open(data)?;
+                file.read_exact(buf)?;
+                if file.read(&mut [0u8])? != 0 {
+                    return Err(ZipError::EntrySizeExceeded(data.file_name.clone()));
+                }
+            }
+        }
+        Ok(())
+    }
 
+    fn data_for_bytes(&self, bytes: &[u8]) -> Option<&Arc<ZipFileData>> {
+        if let Some(data) = self.raw_entries.get(bytes) {
+            return Some(data);
+        }
+        self.entries
+            .get(std::str::from_utf8(bytes).ok()?)
+            .filter(|data| data.file_name_raw == bytes)
+    }
 
-        let reader = find_reader(&self.file, &data, position)?;
+    fn find_entry_data(&self, data: &ZipFileData) -> ZipResult<u64> {
+        let data_start = find_data_start(&self.storage, data)?;
+        if data_start.saturating_add(data.compressed_size) > self.storage.len()? {
+            return Err(ZipError::InvalidArchive("Entry data extends past the end of the archive"));
+        }
+        Ok(data_start)
+    }
 
-        Ok(ZipFile::new(reader, data.clone()))
+    fn read_through_cache(&self, cache: &EntryCache, data: &Arc<ZipFileData>) -> ZipResult<Arc<[u8]>> {
+        if let Some(contents) = cache.get(self.id, &data.file_name) {
+            return Ok(contents);
+        }
+        // The cache only takes entries within its budget, so the declared size is safe to reserve.
+        let mut contents = Vec::with_capacity(data.uncompressed_size as usize);
+        self.open(data)?.read_to_end(&mut contents)?;
+        let contents: Arc<[u8]> = contents.into();
+        cache.insert(self.id, &data.file_name, contents.clone());
+        Ok(contents)
+    }
+
+    fn open(&self, data: &Arc<ZipFileData>) -> ZipResult<ZipFile> {
+        if !self.is_supported(data) {
+            return Err(ZipError::UnsupportedEntry(data.file_name.clone(), data.compression_method.to_u16()));
+        }
+
+        let data_start = self.find_entry_data(data)?;
+        let reader = find_reader(&self.storage, &self.codecs, data, data_start, self.deflate_buffer_size)?;
+
+        let max_size = self.limits.bounds_output().then_some(data.uncompressed_size);
+
+        Ok(ZipFile::new(reader, data.clone(), max_size))
+    }
+}
+
+fn check_entry_size(data: &ZipFileData, size: usize) -> ZipResult<()> {
+    if (size as u64) < data.uncompressed_size {
+        return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+    }
+    if size as u64 > data.uncompressed_size {
+        return Err(ZipError::EntrySizeExceeded(data.file_name.clone()));
+    }
+    Ok(())
+}
+
+impl Drop for ZipArchive {
+    fn drop(&mut self) {
+        if let Some(cache) = &self.entry_cache {
+            cache.remove_archive(self.id);
+        }
     }
 }
 
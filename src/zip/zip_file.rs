@@ -1,43 +1,74 @@
-use std::fs::File;
 use std::io;
 use std::io::{Read, Seek, SeekFrom};
+use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::{Duration, SystemTime};
 use byteorder::{LittleEndian, ReadBytesExt};
-use parking_lot::RwLock;
+use flate2::Crc;
+#[cfg(feature = "bzip2")]
+use crate::zip::bzip2::Bzip2Reader;
+use crate::zip::codec::CodecRegistry;
 use crate::zip::cp437::FromCp437;
 use crate::zip::deflate::DeflateReader;
+#[cfg(feature = "deflate64")]
+use crate::zip::deflate64::Deflate64Reader;
+#[cfg(feature = "lzma")]
+use crate::zip::lzma::LzmaReader;
 use crate::zip::plaintext::PlaintextReader;
 use crate::zip::result::{ZipError, ZipResult};
 use crate::zip::spec;
-use crate::zip::spec::{AesMode, AesVendorVersion, CompressionMethod};
+use crate::zip::spec::{AesMode, AesVendorVersion, CompressionMethod, Parsed};
+use crate::zip::storage::Storage;
+#[cfg(feature = "xz")]
+use crate::zip::xz::XzReader;
+#[cfg(feature = "zstd")]
+use crate::zip::zstd::ZstdReader;
 
 pub struct ZipFile {
     reader: ZipFileReader,
     data: Arc<ZipFileData>,
+    max_size: Option<u64>,
+    read_size: u64,
 }
 
 impl ZipFile {
-    pub fn new(reader: ZipFileReader, data: Arc<ZipFileData>) -> Self {
+    pub fn new(reader: ZipFileReader, data: Arc<ZipFileData>, max_size: Option<u64>) -> Self {
         Self {
             reader,
             data,
+            max_size,
+            read_size: 0,
         }
     }
 
     pub fn len(&self) -> u64 {
-        return self.data.compressed_size;
+        self.data.compressed_size
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
     }
 }
 
 impl Read for ZipFile {
     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
-        self.reader.read(buf)
+        let size = self.reader.read(buf)?;
+        self.read_size += size as u64;
+        if self.max_size.is_some_and(|max| self.read_size > max) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                ZipError::EntrySizeExceeded(self.data.file_name.clone()),
+            ));
+        }
+        Ok(size)
     }
 }
 
 impl Seek for ZipFile {
     fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
-        self.reader.seek(pos)
+        let position = self.reader.seek(pos)?;
+        self.read_size = position;
+        Ok(position)
     }
 }
 
@@ -46,20 +77,130 @@ pub struct ZipFileData {
     pub compressed_size: u64,
     pub uncompressed_size: u64,
     pub file_name: String,
+    // Name as stored in the central directory.
+    pub file_name_raw: Vec<u8>,
+    // Whether `file_name` was decoded from `file_name_raw` with a legacy code page, rather than
+    // taken from UTF-8.
+    pub legacy_name: bool,
+    pub file_comment: String,
     pub extra_field: Vec<u8>,
     pub header_start: u64,
+    // Disk of a split archive holding the local header, `header_start` is already resolved.
+    pub disk_number: u32,
     pub central_header_start: u64,
     pub large_file: bool,
     pub aes_mode: Option<(AesMode, AesVendorVersion)>,
+    pub version_made_by: u16,
+    pub last_mod_time: u16,
+    pub last_mod_date: u16,
+    pub crc32: u32,
+    pub external_attributes: u32,
 }
 
+impl ZipFileData {
+    pub fn is_dir(&self) -> bool {
+        self.file_name.ends_with('/') || self.file_name.ends_with('\\')
+    }
+
+    pub fn is_symlink(&self) -> bool {
+        self.unix_mode().is_some_and(|mode| mode & spec::S_IFMT == spec::S_IFLNK)
+    }
+
+    pub fn unix_mode(&self) -> Option<u32> {
+        if self.external_attributes == 0 {
+            return None;
+        }
+        match self.version_made_by >> 8 {
+            spec::SYSTEM_UNIX | spec::SYSTEM_OSX => {
+                Some(self.external_attributes >> 16).filter(|mode| *mode != 0)
+            }
+            spec::SYSTEM_DOS => {
+                let mut mode = if self.external_attributes & 0x10 != 0 {
+                    spec::S_IFDIR | 0o775
+                } else {
+                    spec::S_IFREG | 0o664
+                };
+                // Read-only attribute
+                if self.external_attributes & 0x01 != 0 {
+                    mode &= !0o222;
+                }
+                Some(mode)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn last_modified(&self) -> Option<SystemTime> {
+        let year = (self.last_mod_date >> 9) as i64 + 1980;
+        let month = ((self.last_mod_date >> 5) & 0x0f) as i64;
+        let day = (self.last_mod_date & 0x1f) as i64;
+        let hour = (self.last_mod_time >> 11) as u64;
+        let minute = ((self.last_mod_time >> 5) & 0x3f) as u64;
+        let second = ((self.last_mod_time & 0x1f) * 2) as u64;
+        if !(1..=12).contains(&month) || !(1..=31).contains(&day) || hour > 23 || minute > 59 || second > 59 {
+            return None;
+        }
+
+        // Days since the unix epoch for the proleptic gregorian calendar.
+        let y = if month <= 2 { year - 1 } else { year };
+        let era = y.div_euclid(400);
+        let yoe = y - era * 400;
+        let mp = (month + 9) % 12;
+        let doy = (153 * mp + 2) / 5 + day - 1;
+        let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+        let days = (era * 146097 + doe - 719468) as u64;
+
+        let seconds = days * 86400 + hour * 3600 + minute * 60 + second;
+        Some(SystemTime::UNIX_EPOCH + Duration::from_secs(seconds))
+    }
+
+    // Returns the entry name as a relative path that cannot escape the directory it is joined to,
+    // or None when the name is absolute, contains `..`, a drive letter or a NUL byte.
+    pub fn enclosed_name(&self) -> Option<PathBuf> {
+        if self.file_name.contains('\0') || self.file_name.starts_with(['/', '\\']) {
+            return None;
+        }
+        let mut path = PathBuf::new();
+        for component in self.file_name.split(['/', '\\']) {
+            match component {
+                "" | "." => {}
+                ".." => return None,
+                c if c.len() >= 2 && c.as_bytes()[1] == b':' && c.as_bytes()[0].is_ascii_alphabetic() => return None,
+                c if cfg!(windows) && c.contains(':') => return None,
+                c => path.push(c),
+            }
+        }
+        Some(path)
+    }
+}
+
+// Reads a central directory header whose signature has already been consumed.
 pub fn central_header_to_zip_file_inner<R: Read>(reader: &mut R, archive_offset: u64, central_header_start: u64) -> ZipResult<ZipFileData> {
+    let mut header = spec::CENTRAL_DIRECTORY_HEADER_SIGNATURE.to_le_bytes().to_vec();
+    header.resize(spec::CENTRAL_DIRECTORY_HEADER_SIZE, 0);
+    reader.read_exact(&mut header[4..])?;
+    if let Parsed::Incomplete(size) = parse_central_header(&header, archive_offset, central_header_start)? {
+        header.resize(size, 0);
+        reader.read_exact(&mut header[spec::CENTRAL_DIRECTORY_HEADER_SIZE..])?;
+    }
+    parse_central_header(&header, archive_offset, central_header_start)?.complete()
+}
+
+pub fn parse_central_header(buf: &[u8], archive_offset: u64, central_header_start: u64) -> ZipResult<Parsed<ZipFileData>> {
+    if buf.len() < spec::CENTRAL_DIRECTORY_HEADER_SIZE {
+        return Ok(Parsed::Incomplete(spec::CENTRAL_DIRECTORY_HEADER_SIZE));
+    }
+    let mut reader = buf;
+    let signature = reader.read_u32::<LittleEndian>()?;
+    if signature != spec::CENTRAL_DIRECTORY_HEADER_SIGNATURE {
+        return Err(ZipError::InvalidArchive("Invalid Central Directory header"));
+    }
     let version_made_by = reader.read_u16::<LittleEndian>()?;
     let _version_to_extract = reader.read_u16::<LittleEndian>()?;
     let flags = reader.read_u16::<LittleEndian>()?;
-    let encrypted = flags & 1 == 1;
+    let _encrypted = flags & 1 == 1;
     let is_utf8 = flags & (1 << 11) != 0;
-    let using_data_descriptor = flags & (1 << 3) != 0;
+    let _using_data_descriptor = flags & (1 << 3) != 0;
     let compression_method = reader.read_u16::<LittleEndian>()?;
     let last_mod_time = reader.read_u16::<LittleEndian>()?;
     let last_mod_date = reader.read_u16::<LittleEndian>()?;
@@ -69,24 +210,26 @@ pub fn central_header_to_zip_file_inner<R: Read>(reader: &mut R, archive_offset:
     let file_name_length = reader.read_u16::<LittleEndian>()? as usize;
     let extra_field_length = reader.read_u16::<LittleEndian>()? as usize;
     let file_comment_length = reader.read_u16::<LittleEndian>()? as usize;
-    let _disk_number = reader.read_u16::<LittleEndian>()?;
+    let disk_number = reader.read_u16::<LittleEndian>()?;
     let _internal_file_attributes = reader.read_u16::<LittleEndian>()?;
     let external_file_attributes = reader.read_u32::<LittleEndian>()?;
     let offset = reader.read_u32::<LittleEndian>()? as u64;
-    let mut file_name_raw = vec![0; file_name_length];
-    reader.read_exact(&mut file_name_raw)?;
-    let mut extra_field = vec![0; extra_field_length];
-    reader.read_exact(&mut extra_field)?;
-    let mut file_comment_raw = vec![0; file_comment_length];
-    reader.read_exact(&mut file_comment_raw)?;
+    let size = spec::CENTRAL_DIRECTORY_HEADER_SIZE + file_name_length + extra_field_length + file_comment_length;
+    if buf.len() < size {
+        return Ok(Parsed::Incomplete(size));
+    }
+    let (file_name_raw, reader) = reader.split_at(file_name_length);
+    let (extra_field, reader) = reader.split_at(extra_field_length);
+    let file_comment_raw = &reader[..file_comment_length];
+    let extra_field = extra_field.to_vec();
 
     let file_name = match is_utf8 {
-        true => String::from_utf8_lossy(&file_name_raw).into_owned(),
-        false => file_name_raw.clone().from_cp437(),
+        true => String::from_utf8_lossy(file_name_raw).into_owned(),
+        false => file_name_raw.from_cp437().into_owned(),
     };
     let file_comment = match is_utf8 {
-        true => String::from_utf8_lossy(&file_comment_raw).into_owned(),
-        false => file_comment_raw.from_cp437(),
+        true => String::from_utf8_lossy(file_comment_raw).into_owned(),
+        false => file_comment_raw.from_cp437().into_owned(),
     };
 
     let mut result = ZipFileData {
@@ -96,58 +239,102 @@ pub fn central_header_to_zip_file_inner<R: Read>(reader: &mut R, archive_offset:
         compressed_size: compressed_size as u64,
         uncompressed_size: uncompressed_size as u64,
         file_name,
+        file_name_raw: file_name_raw.to_vec(),
+        legacy_name: !is_utf8,
+        file_comment,
         extra_field,
         header_start: offset,
+        disk_number: disk_number as u32,
         central_header_start,
         large_file: false,
         aes_mode: None,
+        version_made_by,
+        last_mod_time,
+        last_mod_date,
+        crc32,
+        external_attributes: external_file_attributes,
     };
 
-    match parse_extra_field(&mut result) {
+    match parse_extra_field(&mut result, file_name_raw, file_comment_raw) {
         Ok(..) | Err(ZipError::Io(..)) => {}
         Err(e) => return Err(e),
     }
 
-    match result.compression_method {
-        CompressionMethod::Unsupported(method) => {
-            return Err(ZipError::UnsupportedCompressionMethod(method));
-        }
-        _ => {}
-    }
-
     // Account for shifted zip offsets.
     result.header_start = result
         .header_start
         .checked_add(archive_offset)
         .ok_or(ZipError::InvalidArchive("Archive header is too large"))?;
 
-    Ok(result)
+    Ok(Parsed::Complete(result, size))
 }
 
-pub fn find_reader(file: &Arc<RwLock<File>>, data: &ZipFileData, position: u64) -> ZipResult<ZipFileReader> {
-    let data_start = {
-        let mut file = &*file.write();
-        file.seek(io::SeekFrom::Start(position + 22))?;
-        let file_name_length = file.read_u16::<LittleEndian>()? as u64;
-        let extra_field_length = file.read_u16::<LittleEndian>()? as u64;
-        let magic_and_header = 4 + 22 + 2 + 2;
-        data.header_start + magic_and_header + file_name_length + extra_field_length
-    };
+// Checks the local header of `data` and returns the offset of its compressed bytes.
+pub fn find_data_start(storage: &Arc<dyn Storage>, data: &ZipFileData) -> ZipResult<u64> {
+    let mut header = [0u8; 30];
+    storage.read_exact_at(&mut header, data.header_start)?;
+    let mut header = io::Cursor::new(header);
+    if header.read_u32::<LittleEndian>()? != spec::LOCAL_FILE_HEADER_SIGNATURE {
+        return Err(ZipError::InvalidArchive("Invalid local file header"));
+    }
+    header.set_position(26);
+    let file_name_length = header.read_u16::<LittleEndian>()? as u64;
+    let extra_field_length = header.read_u16::<LittleEndian>()? as u64;
+    let magic_and_header = 4 + 22 + 2 + 2;
+    Ok(data.header_start + magic_and_header + file_name_length + extra_field_length)
+}
+
+pub fn find_reader(
+    storage: &Arc<dyn Storage>,
+    codecs: &CodecRegistry,
+    data: &ZipFileData,
+    data_start: u64,
+    deflate_buffer_size: usize,
+) -> ZipResult<ZipFileReader> {
+    if let Some(codec) = codecs.get(data.compression_method.to_u16()) {
+        let reader = PlaintextReader::new(storage.clone(), data_start, data_start + data.compressed_size);
+        return Ok(ZipFileReader::Custom(codec.decoder(Box::new(reader), data)?));
+    }
 
     match data.compression_method {
         CompressionMethod::Stored => {
-            return Ok(ZipFileReader::Stored(PlaintextReader::new(file.clone(), data_start, data_start + data.compressed_size)));
+            Ok(ZipFileReader::Stored(PlaintextReader::new(storage.clone(), data_start, data_start + data.compressed_size)))
         }
         CompressionMethod::Deflate => {
-            return Ok(ZipFileReader::Deflate(DeflateReader::new(file.clone(), data_start, data_start + data.compressed_size)));
+            Ok(ZipFileReader::Deflate(DeflateReader::new(storage.clone(), data_start, data_start + data.compressed_size, deflate_buffer_size)))
+        }
+        #[cfg(feature = "deflate64")]
+        CompressionMethod::Deflate64 => {
+            let reader = PlaintextReader::new(storage.clone(), data_start, data_start + data.compressed_size);
+            Ok(ZipFileReader::Deflate64(Deflate64Reader::new(reader)))
+        }
+        #[cfg(feature = "bzip2")]
+        CompressionMethod::Bzip2 => {
+            let reader = PlaintextReader::new(storage.clone(), data_start, data_start + data.compressed_size);
+            Ok(ZipFileReader::Bzip2(Bzip2Reader::new(reader)))
+        }
+        #[cfg(feature = "zstd")]
+        CompressionMethod::Zstd => {
+            let reader = PlaintextReader::new(storage.clone(), data_start, data_start + data.compressed_size);
+            Ok(ZipFileReader::Zstd(ZstdReader::new(reader)?))
+        }
+        #[cfg(feature = "lzma")]
+        CompressionMethod::Lzma => {
+            let reader = PlaintextReader::new(storage.clone(), data_start, data_start + data.compressed_size);
+            Ok(ZipFileReader::Lzma(LzmaReader::new(reader, data.uncompressed_size)?))
+        }
+        #[cfg(feature = "xz")]
+        CompressionMethod::Xz => {
+            let reader = PlaintextReader::new(storage.clone(), data_start, data_start + data.compressed_size);
+            Ok(ZipFileReader::Xz(XzReader::new(reader)))
         }
         CompressionMethod::Unsupported(method) => {
-            return Err(ZipError::UnsupportedCompressionMethod(method));
+            Err(ZipError::UnsupportedCompressionMethod(method))
         }
     }
 }
 
-fn parse_extra_field(file: &mut ZipFileData) -> ZipResult<()> {
+fn parse_extra_field(file: &mut ZipFileData, file_name_raw: &[u8], file_comment_raw: &[u8]) -> ZipResult<()> {
     let mut reader = io::Cursor::new(&file.extra_field);
 
     while (reader.position() as usize) < file.extra_field.len() {
@@ -170,6 +357,10 @@ fn parse_extra_field(file: &mut ZipFileData) -> ZipResult<()> {
                     file.header_start = reader.read_u64::<LittleEndian>()?;
                     len_left -= 8;
                 }
+                if file.disk_number == 0xFFFF {
+                    file.disk_number = reader.read_u32::<LittleEndian>()?;
+                    len_left -= 4;
+                }
             }
             0x9901 => {
                 // AES
@@ -200,6 +391,22 @@ fn parse_extra_field(file: &mut ZipFileData) -> ZipResult<()> {
                     CompressionMethod::from_u16(compression_method)
                 };
             }
+            0x7075 | 0x6375 => {
+                // Info-ZIP Unicode Path and Unicode Comment
+                let mut field = vec![0u8; len as usize];
+                reader.read_exact(&mut field)?;
+                len_left = 0;
+                let original = if kind == 0x7075 { file_name_raw } else { file_comment_raw };
+                if let Some(text) = parse_unicode_extra_field(&field, original) {
+                    match kind {
+                        0x7075 => {
+                            file.file_name = text;
+                            file.legacy_name = false;
+                        }
+                        _ => file.file_comment = text,
+                    }
+                }
+            }
             _ => {
                 // Other fields are ignored
             }
@@ -213,25 +420,77 @@ fn parse_extra_field(file: &mut ZipFileData) -> ZipResult<()> {
     Ok(())
 }
 
+// A version, the CRC32 of the name or comment in the header and its UTF-8 form. A CRC that does not
+// match means the header was rewritten by a tool that did not know the field, which is then stale.
+fn parse_unicode_extra_field(field: &[u8], original: &[u8]) -> Option<String> {
+    let (&version, rest) = field.split_first()?;
+    if version != 1 || rest.len() < 4 {
+        return None;
+    }
+    let (crc32, text) = rest.split_at(4);
+    let mut crc = Crc::new();
+    crc.update(original);
+    if crc.sum().to_le_bytes() != crc32 {
+        return None;
+    }
+    String::from_utf8(text.to_vec()).ok()
+}
+
 pub enum ZipFileReader {
     Stored(PlaintextReader),
     Deflate(DeflateReader),
+    #[cfg(feature = "deflate64")]
+    Deflate64(Deflate64Reader),
+    #[cfg(feature = "bzip2")]
+    Bzip2(Bzip2Reader),
+    #[cfg(feature = "zstd")]
+    Zstd(ZstdReader),
+    #[cfg(feature = "lzma")]
+    Lzma(LzmaReader),
+    #[cfg(feature = "xz")]
+    Xz(XzReader),
+    Custom(Box<dyn Read + Send>),
+    Cached(io::Cursor<Arc<[u8]>>),
 }
 
 impl Read for ZipFileReader {
     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
-        return match self {
+        match self {
             ZipFileReader::Stored(r) => r.read(buf),
             ZipFileReader::Deflate(r) => r.read(buf),
+            #[cfg(feature = "deflate64")]
+            ZipFileReader::Deflate64(r) => r.read(buf),
+            #[cfg(feature = "bzip2")]
+            ZipFileReader::Bzip2(r) => r.read(buf),
+            #[cfg(feature = "zstd")]
+            ZipFileReader::Zstd(r) => r.read(buf),
+            #[cfg(feature = "lzma")]
+            ZipFileReader::Lzma(r) => r.read(buf),
+            #[cfg(feature = "xz")]
+            ZipFileReader::Xz(r) => r.read(buf),
+            ZipFileReader::Custom(r) => r.read(buf),
+            ZipFileReader::Cached(r) => r.read(buf),
         }
     }
 }
 
 impl Seek for ZipFileReader {
     fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
-        return match self {
+        match self {
             ZipFileReader::Stored(r) => r.seek(pos),
             ZipFileReader::Deflate(r) => r.seek(pos),
+            #[cfg(feature = "deflate64")]
+            ZipFileReader::Deflate64(r) => r.seek(pos),
+            #[cfg(feature = "bzip2")]
+            ZipFileReader::Bzip2(r) => r.seek(pos),
+            #[cfg(feature = "zstd")]
+            ZipFileReader::Zstd(r) => r.seek(pos),
+            #[cfg(feature = "lzma")]
+            ZipFileReader::Lzma(r) => r.seek(pos),
+            #[cfg(feature = "xz")]
+            ZipFileReader::Xz(r) => r.seek(pos),
+            ZipFileReader::Custom(_) => Err(io::Error::new(io::ErrorKind::Unsupported, "Seek is not supported for custom codec entries")),
+            ZipFileReader::Cached(r) => r.seek(pos),
         }
     }
 }
\ No newline at end of file
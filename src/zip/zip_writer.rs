@@ -0,0 +1,566 @@
+use std::fs;
+use std::fs::File;
+use std::io;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use byteorder::{LittleEndian, WriteBytesExt};
+use flate2::{Compression, Crc};
+use flate2::write::DeflateEncoder;
+use parking_lot::Mutex;
+use crate::zip::codec::CodecRegistry;
+use crate::zip::result::{ZipError, ZipResult};
+use crate::zip::spec;
+use crate::zip::spec::CompressionMethod;
+
+// Info-ZIP does not write smaller segments either.
+const MIN_SPLIT_SIZE: u64 = 64 * 1024;
+const VERSION_DEFAULT: u16 = 20;
+const VERSION_ZIP64: u16 = 45;
+
+// Receives the segments of an archive as it is written.
+pub trait SegmentSink: Send {
+    // Opens the segment of disk `disk`, numbered from 0. The previous segment is complete.
+    fn create(&mut self, disk: u32) -> io::Result<Box<dyn Write + Send>>;
+
+    // Called once `last_disk`, the last segment, is complete.
+    fn finish(&mut self, _last_disk: u32) -> io::Result<()> {
+        Ok(())
+    }
+
+    // Overwrites the first bytes of segment `disk`, which is complete. Sinks that cannot go back keep
+    // what was written, readers accept either.
+    fn rewrite_start(&mut self, _disk: u32, _bytes: &[u8]) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+// Writes `archive.zip`, or when split `archive.z01`, `archive.z02`, ... followed by `archive.zip`
+// as the last segment, the layout `ZipArchive::open_split` reads back.
+pub struct FileSegments {
+    path: PathBuf,
+    split: bool,
+}
+
+impl FileSegments {
+    pub fn new(path: impl Into<PathBuf>, split: bool) -> Self {
+        Self { path: path.into(), split }
+    }
+
+    fn segment_path(&self, disk: u32) -> PathBuf {
+        match self.split {
+            true => self.path.with_extension(format!("z{:02}", disk as u64 + 1)),
+            false => self.path.clone(),
+        }
+    }
+}
+
+impl SegmentSink for FileSegments {
+    fn create(&mut self, disk: u32) -> io::Result<Box<dyn Write + Send>> {
+        Ok(Box::new(BufWriter::new(File::create(self.segment_path(disk))?)))
+    }
+
+    // Which segment is the last is only known at the end, so it is renamed then.
+    fn finish(&mut self, last_disk: u32) -> io::Result<()> {
+        match self.split {
+            true => fs::rename(self.segment_path(last_disk), &self.path),
+            false => Ok(()),
+        }
+    }
+
+    fn rewrite_start(&mut self, disk: u32, bytes: &[u8]) -> io::Result<()> {
+        let mut file = fs::OpenOptions::new().write(true).open(self.segment_path(disk))?;
+        file.write_all(bytes)
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct ZipWriterOptions {
+    // Largest size of a segment in bytes, 0 writes the archive as a single segment.
+    pub split_size: u64,
+    // Encoders for methods other than stored and deflate, registered ones take precedence.
+    pub codecs: CodecRegistry,
+}
+
+#[derive(Clone, Debug)]
+pub struct FileOptions {
+    pub compression_method: CompressionMethod,
+    pub last_mod_time: u16,
+    pub last_mod_date: u16,
+    // Permission bits, the file type is added from the kind of entry.
+    pub unix_mode: Option<u32>,
+    // Entries of 4 GiB or more need zip64 fields in their local header, which is written before
+    // the size is known.
+    pub large_file: bool,
+}
+
+impl Default for FileOptions {
+    fn default() -> Self {
+        Self {
+            compression_method: CompressionMethod::Deflate,
+            last_mod_time: 0,
+            // 1980-01-01, the earliest date the format can hold.
+            last_mod_date: (1 << 5) | 1,
+            unix_mode: None,
+            large_file: false,
+        }
+    }
+}
+
+// Everything the central directory needs to know about a written entry.
+struct EntryRecord {
+    file_name: String,
+    options: FileOptions,
+    flags: u16,
+    is_dir: bool,
+    disk_number: u32,
+    header_start: u64,
+    crc32: u32,
+    compressed_size: u64,
+    uncompressed_size: u64,
+}
+
+impl EntryRecord {
+    fn version_made_by(&self) -> u16 {
+        let system = match self.options.unix_mode {
+            Some(_) => spec::SYSTEM_UNIX,
+            None => spec::SYSTEM_DOS,
+        };
+        (system << 8) | VERSION_ZIP64
+    }
+
+    fn external_attributes(&self) -> u32 {
+        let dos = if self.is_dir { 0x10 } else { 0 };
+        let kind = if self.is_dir { spec::S_IFDIR } else { spec::S_IFREG };
+        match self.options.unix_mode {
+            Some(mode) => ((kind | (mode & !spec::S_IFMT)) << 16) | dos,
+            None => dos,
+        }
+    }
+
+    // The data descriptor carries the crc and sizes, they are unknown when the header is written.
+    fn local_header(&self) -> io::Result<Vec<u8>> {
+        let large_file = self.options.large_file;
+        let mut header = Vec::with_capacity(30 + self.file_name.len() + 20);
+        header.write_u32::<LittleEndian>(spec::LOCAL_FILE_HEADER_SIGNATURE)?;
+        header.write_u16::<LittleEndian>(if large_file { VERSION_ZIP64 } else { VERSION_DEFAULT })?;
+        header.write_u16::<LittleEndian>(self.flags)?;
+        header.write_u16::<LittleEndian>(self.options.compression_method.to_u16())?;
+        header.write_u16::<LittleEndian>(self.options.last_mod_time)?;
+        header.write_u16::<LittleEndian>(self.options.last_mod_date)?;
+        header.write_u32::<LittleEndian>(0)?;
+        let size = if large_file { spec::ZIP64_BYTES_THR as u32 } else { 0 };
+        header.write_u32::<LittleEndian>(size)?;
+        header.write_u32::<LittleEndian>(size)?;
+        header.write_u16::<LittleEndian>(self.file_name.len() as u16)?;
+        header.write_u16::<LittleEndian>(if large_file { 20 } else { 0 })?;
+        header.write_all(self.file_name.as_bytes())?;
+        if large_file {
+            header.write_u16::<LittleEndian>(0x0001)?;
+            header.write_u16::<LittleEndian>(16)?;
+            header.write_u64::<LittleEndian>(0)?;
+            header.write_u64::<LittleEndian>(0)?;
+        }
+        Ok(header)
+    }
+
+    fn data_descriptor(&self) -> io::Result<Vec<u8>> {
+        let mut descriptor = Vec::with_capacity(24);
+        descriptor.write_u32::<LittleEndian>(spec::DATA_DESCRIPTOR_SIGNATURE)?;
+        descriptor.write_u32::<LittleEndian>(self.crc32)?;
+        if self.options.large_file {
+            descriptor.write_u64::<LittleEndian>(self.compressed_size)?;
+            descriptor.write_u64::<LittleEndian>(self.uncompressed_size)?;
+        } else {
+            descriptor.write_u32::<LittleEndian>(self.compressed_size as u32)?;
+            descriptor.write_u32::<LittleEndian>(self.uncompressed_size as u32)?;
+        }
+        Ok(descriptor)
+    }
+
+    fn central_header(&self) -> io::Result<Vec<u8>> {
+        // Fields too large for the header move to the zip64 extra field, in this order.
+        let mut zip64 = Vec::new();
+        if self.uncompressed_size >= spec::ZIP64_BYTES_THR {
+            zip64.write_u64::<LittleEndian>(self.uncompressed_size)?;
+        }
+        if self.compressed_size >= spec::ZIP64_BYTES_THR {
+            zip64.write_u64::<LittleEndian>(self.compressed_size)?;
+        }
+        if self.header_start >= spec::ZIP64_BYTES_THR {
+            zip64.write_u64::<LittleEndian>(self.header_start)?;
+        }
+        if self.disk_number >= 0xFFFF {
+            zip64.write_u32::<LittleEndian>(self.disk_number)?;
+        }
+        let extra_len = if zip64.is_empty() { 0 } else { zip64.len() + 4 };
+        let version_needed = if self.options.large_file || !zip64.is_empty() { VERSION_ZIP64 } else { VERSION_DEFAULT };
+
+        let mut header = Vec::with_capacity(spec::CENTRAL_DIRECTORY_HEADER_SIZE + self.file_name.len() + extra_len);
+        header.write_u32::<LittleEndian>(spec::CENTRAL_DIRECTORY_HEADER_SIGNATURE)?;
+        header.write_u16::<LittleEndian>(self.version_made_by())?;
+        header.write_u16::<LittleEndian>(version_needed)?;
+        header.write_u16::<LittleEndian>(self.flags)?;
+        header.write_u16::<LittleEndian>(self.options.compression_method.to_u16())?;
+        header.write_u16::<LittleEndian>(self.options.last_mod_time)?;
+        header.write_u16::<LittleEndian>(self.options.last_mod_date)?;
+        header.write_u32::<LittleEndian>(self.crc32)?;
+        header.write_u32::<LittleEndian>(self.compressed_size.min(spec::ZIP64_BYTES_THR) as u32)?;
+        header.write_u32::<LittleEndian>(self.uncompressed_size.min(spec::ZIP64_BYTES_THR) as u32)?;
+        header.write_u16::<LittleEndian>(self.file_name.len() as u16)?;
+        header.write_u16::<LittleEndian>(extra_len as u16)?;
+        header.write_u16::<LittleEndian>(0)?;
+        header.write_u16::<LittleEndian>(self.disk_number.min(0xFFFF) as u16)?;
+        header.write_u16::<LittleEndian>(0)?;
+        header.write_u32::<LittleEndian>(self.external_attributes())?;
+        header.write_u32::<LittleEndian>(self.header_start.min(spec::ZIP64_BYTES_THR) as u32)?;
+        header.write_all(self.file_name.as_bytes())?;
+        if !zip64.is_empty() {
+            header.write_u16::<LittleEndian>(0x0001)?;
+            header.write_u16::<LittleEndian>(zip64.len() as u16)?;
+            header.write_all(&zip64)?;
+        }
+        Ok(header)
+    }
+}
+
+struct Output {
+    sink: Box<dyn SegmentSink>,
+    segment: Box<dyn Write + Send>,
+    // `u64::MAX` when the archive is not split.
+    split_size: u64,
+    disk: u32,
+    // Bytes written to the current segment.
+    position: u64,
+    // Bytes of entry data written since the current entry started.
+    data_size: u64,
+}
+
+impl Output {
+    fn next_segment(&mut self) -> io::Result<()> {
+        self.segment.flush()?;
+        self.disk = self
+            .disk
+            .checked_add(1)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Archive has too many segments"))?;
+        self.segment = self.sink.create(self.disk)?;
+        self.position = 0;
+        Ok(())
+    }
+
+    // Headers and records never span segments, a new one is started when `len` bytes do not fit.
+    // Returns the disk and offset they will be written at.
+    fn reserve(&mut self, len: u64) -> io::Result<(u32, u64)> {
+        if len > self.split_size {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "Record is larger than a segment"));
+        }
+        if self.position.saturating_add(len) > self.split_size {
+            self.next_segment()?;
+        }
+        Ok((self.disk, self.position))
+    }
+
+    fn write_record(&mut self, record: &[u8]) -> io::Result<(u32, u64)> {
+        let location = self.reserve(record.len() as u64)?;
+        self.segment.write_all(record)?;
+        self.position += record.len() as u64;
+        Ok(location)
+    }
+}
+
+// Entry data, which unlike records continues in the next segment when the current one is full.
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        if self.position >= self.split_size {
+            self.next_segment()?;
+        }
+        let limit = (self.split_size - self.position).min(buf.len() as u64) as usize;
+        let size = self.segment.write(&buf[..limit])?;
+        self.position += size as u64;
+        self.data_size += size as u64;
+        Ok(size)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.segment.flush()
+    }
+}
+
+// Codec encoders take ownership of what they write to, this hands them the shared output.
+#[derive(Clone)]
+struct SharedOutput(Arc<Mutex<Output>>);
+
+impl Write for SharedOutput {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.lock().flush()
+    }
+}
+
+enum EntryWriter {
+    Stored(SharedOutput),
+    Deflate(DeflateEncoder<SharedOutput>),
+    Custom(Box<dyn Write + Send>),
+}
+
+impl EntryWriter {
+    fn finish(self) -> io::Result<()> {
+        match self {
+            EntryWriter::Stored(mut w) => w.flush(),
+            EntryWriter::Deflate(w) => w.finish()?.flush(),
+            // Encoders write their trailer when dropped.
+            EntryWriter::Custom(mut w) => w.flush(),
+        }
+    }
+}
+
+impl Write for EntryWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            EntryWriter::Stored(w) => w.write(buf),
+            EntryWriter::Deflate(w) => w.write(buf),
+            EntryWriter::Custom(w) => w.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            EntryWriter::Stored(w) => w.flush(),
+            EntryWriter::Deflate(w) => w.flush(),
+            EntryWriter::Custom(w) => w.flush(),
+        }
+    }
+}
+
+struct OpenEntry {
+    record: EntryRecord,
+    writer: EntryWriter,
+    crc: Crc,
+    size: u64,
+}
+
+// Writes an archive entry by entry, optionally split into segments of at most `split_size` bytes.
+// Data written through `Write` goes to the entry started last. An archive is only complete once
+// `finish` returns.
+pub struct ZipWriter {
+    output: Arc<Mutex<Output>>,
+    codecs: CodecRegistry,
+    entries: Vec<EntryRecord>,
+    current: Option<OpenEntry>,
+}
+
+impl ZipWriter {
+    pub fn create(path: impl AsRef<Path>, options: ZipWriterOptions) -> ZipResult<ZipWriter> {
+        let split = options.split_size > 0;
+        Self::new(FileSegments::new(path.as_ref(), split), options)
+    }
+
+    pub fn new(sink: impl SegmentSink + 'static, options: ZipWriterOptions) -> ZipResult<ZipWriter> {
+        let ZipWriterOptions { split_size, codecs } = options;
+        if split_size > 0 && split_size < MIN_SPLIT_SIZE {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "Segments must be at least 64 KiB").into());
+        }
+
+        let mut sink: Box<dyn SegmentSink> = Box::new(sink);
+        let segment = sink.create(0)?;
+        let mut output = Output {
+            sink,
+            segment,
+            split_size: if split_size == 0 { u64::MAX } else { split_size },
+            disk: 0,
+            position: 0,
+            data_size: 0,
+        };
+        if split_size > 0 {
+            output.write_record(&spec::SPLIT_ARCHIVE_SIGNATURE.to_le_bytes())?;
+        }
+
+        Ok(ZipWriter {
+            output: Arc::new(Mutex::new(output)),
+            codecs,
+            entries: Vec::new(),
+            current: None,
+        })
+    }
+
+    pub fn start_file(&mut self, name: impl Into<String>, options: FileOptions) -> ZipResult<()> {
+        self.finish_file()?;
+        let file_name = name.into();
+        let is_dir = file_name.ends_with('/');
+        self.start_entry(file_name, is_dir, options)
+    }
+
+    pub fn add_directory(&mut self, name: impl Into<String>, options: FileOptions) -> ZipResult<()> {
+        self.finish_file()?;
+        let mut file_name = name.into();
+        if !file_name.ends_with('/') {
+            file_name.push('/');
+        }
+        let options = FileOptions { compression_method: CompressionMethod::Stored, ..options };
+        self.start_entry(file_name, true, options)?;
+        self.finish_file()
+    }
+
+    // Completes the last entry, writes the central directory and hands the last segment to the
+    // sink. Returns the number of segments written.
+    pub fn finish(mut self) -> ZipResult<u32> {
+        self.finish_file()?;
+        let mut output = self.output.lock();
+
+        let mut directory_start = None;
+        let mut directory_size = 0u64;
+        let mut header_disks = Vec::with_capacity(self.entries.len());
+        for entry in &self.entries {
+            let header = entry.central_header()?;
+            let location = output.write_record(&header)?;
+            directory_start.get_or_insert(location);
+            header_disks.push(location.0);
+            directory_size += header.len() as u64;
+        }
+
+        // The end records go to one segment together, with room for the zip64 ones.
+        let end_size = spec::ZIP64_CENTRAL_DIRECTORY_END_SIZE
+            + spec::ZIP64_CENTRAL_DIRECTORY_END_LOCATOR_SIZE
+            + spec::CENTRAL_DIRECTORY_END_SIZE;
+        let (disk, position) = output.reserve(end_size)?;
+        let (directory_disk, directory_offset) = directory_start.unwrap_or((disk, position));
+        let files = self.entries.len() as u64;
+        let files_on_disk = header_disks.iter().filter(|header_disk| **header_disk == disk).count() as u64;
+
+        let zip64 = files >= spec::ZIP64_ENTRY_THR as u64
+            || directory_size >= spec::ZIP64_BYTES_THR
+            || directory_offset >= spec::ZIP64_BYTES_THR
+            || disk >= 0xFFFF
+            || directory_disk >= 0xFFFF;
+
+        let mut end = Vec::with_capacity(end_size as usize);
+        if zip64 {
+            spec::Zip64CentralDirectoryEnd {
+                version_made_by: VERSION_ZIP64,
+                version_needed_to_extract: VERSION_ZIP64,
+                disk_number: disk,
+                disk_with_central_directory: directory_disk,
+                number_of_files_on_this_disk: files_on_disk,
+                number_of_files: files,
+                central_directory_size: directory_size,
+                central_directory_offset: directory_offset,
+            }
+            .write(&mut end)?;
+            spec::Zip64CentralDirectoryEndLocator {
+                disk_with_central_directory: disk,
+                end_of_central_directory_offset: position,
+                number_of_disks: disk + 1,
+            }
+            .write(&mut end)?;
+        }
+        spec::CentralDirectoryEnd {
+            disk_number: disk.min(0xFFFF) as u16,
+            disk_with_central_directory: directory_disk.min(0xFFFF) as u16,
+            number_of_files_on_this_disk: files_on_disk.min(0xFFFF) as u16,
+            number_of_files: files.min(0xFFFF) as u16,
+            central_directory_size: directory_size.min(spec::ZIP64_BYTES_THR) as u32,
+            central_directory_offset: directory_offset.min(spec::ZIP64_BYTES_THR) as u32,
+            zip_file_comment: Vec::new(),
+        }
+        .write(&mut end)?;
+        output.write_record(&end)?;
+
+        output.segment.flush()?;
+        if output.split_size != u64::MAX && disk == 0 {
+            // APPNOTE 8.5.4: an archive that was to be split but fit in one segment is marked as such.
+            output.sink.rewrite_start(0, &spec::SINGLE_SEGMENT_SIGNATURE.to_le_bytes())?;
+        }
+        output.sink.finish(disk)?;
+        Ok(disk + 1)
+    }
+
+    fn start_entry(&mut self, file_name: String, is_dir: bool, options: FileOptions) -> ZipResult<()> {
+        if file_name.len() > u16::MAX as usize {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "File name is longer than 65535 bytes").into());
+        }
+        let method = options.compression_method;
+        let codec = self.codecs.get(method.to_u16());
+        if codec.is_none() && !matches!(method, CompressionMethod::Stored | CompressionMethod::Deflate) {
+            return Err(ZipError::UnsupportedCompressionMethod(method.to_u16()));
+        }
+
+        // Bit 3: sizes and crc follow the data, bit 11: the name is UTF-8.
+        let mut flags = 1 << 3;
+        if !file_name.is_ascii() {
+            flags |= 1 << 11;
+        }
+        let mut record = EntryRecord {
+            file_name,
+            options,
+            flags,
+            is_dir,
+            disk_number: 0,
+            header_start: 0,
+            crc32: 0,
+            compressed_size: 0,
+            uncompressed_size: 0,
+        };
+
+        {
+            let mut output = self.output.lock();
+            (record.disk_number, record.header_start) = output.write_record(&record.local_header()?)?;
+            output.data_size = 0;
+        }
+
+        let shared = SharedOutput(self.output.clone());
+        let writer = match codec {
+            Some(codec) => EntryWriter::Custom(codec.encoder(Box::new(shared))?),
+            None if method == CompressionMethod::Stored => EntryWriter::Stored(shared),
+            None => EntryWriter::Deflate(DeflateEncoder::new(shared, Compression::default())),
+        };
+        self.current = Some(OpenEntry { record, writer, crc: Crc::new(), size: 0 });
+        Ok(())
+    }
+
+    fn finish_file(&mut self) -> ZipResult<()> {
+        let Some(OpenEntry { mut record, writer, crc, size }) = self.current.take() else {
+            return Ok(());
+        };
+        writer.finish()?;
+
+        let mut output = self.output.lock();
+        record.crc32 = crc.sum();
+        record.compressed_size = output.data_size;
+        record.uncompressed_size = size;
+        if !record.options.large_file
+            && (record.compressed_size >= spec::ZIP64_BYTES_THR || record.uncompressed_size >= spec::ZIP64_BYTES_THR)
+        {
+            return Err(ZipError::LargeFileRequired(record.file_name));
+        }
+        output.write_record(&record.data_descriptor()?)?;
+        drop(output);
+
+        self.entries.push(record);
+        Ok(())
+    }
+}
+
+impl Write for ZipWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let Some(entry) = &mut self.current else {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "No file has been started"));
+        };
+        let size = entry.writer.write(buf)?;
+        entry.crc.update(&buf[..size]);
+        entry.size += size as u64;
+        Ok(size)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match &mut self.current {
+            Some(entry) => entry.writer.flush(),
+            None => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,27 @@
+use std::io::{BufReader, Error, ErrorKind, Read, Seek, SeekFrom};
+use zstd::stream::read::Decoder;
+use crate::zip::plaintext::PlaintextReader;
+
+pub struct ZstdReader {
+    decoder: Decoder<'static, BufReader<PlaintextReader>>,
+}
+
+impl ZstdReader {
+    pub fn new(reader: PlaintextReader) -> std::io::Result<Self> {
+        Ok(Self {
+            decoder: Decoder::new(reader)?,
+        })
+    }
+}
+
+impl Read for ZstdReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.decoder.read(buf)
+    }
+}
+
+impl Seek for ZstdReader {
+    fn seek(&mut self, _pos: SeekFrom) -> std::io::Result<u64> {
+        Err(Error::new(ErrorKind::Unsupported, "Seek is not supported for zstd entries"))
+    }
+}
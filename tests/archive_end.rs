@@ -0,0 +1,73 @@
+use std::sync::Arc;
+use vfs_rs::zip::result::ZipResult;
+use vfs_rs::zip::zip_archive::{ZipArchive, ZipArchiveOptions};
+
+mod common;
+
+fn archive() -> Vec<u8> {
+    common::build_archive(
+        &[("a.txt", common::STORED, b"alpha"), ("b.txt", common::DEFLATED, b"beta beta beta")],
+        &[],
+        false,
+    )
+    .unwrap()
+}
+
+fn open(bytes: Vec<u8>) -> ZipResult<ZipArchive> {
+    ZipArchive::from_storage(Arc::new(bytes), ZipArchiveOptions::default())
+}
+
+fn check_entries(archive: &ZipArchive) {
+    assert_eq!(archive.entries.len(), 2);
+    assert_eq!(archive.read_to_vec("a.txt").unwrap(), b"alpha");
+    assert_eq!(archive.read_to_vec("b.txt").unwrap(), b"beta beta beta");
+}
+
+// An end of central directory record for a single entry directory at offset 0, with a comment of
+// `comment_length` bytes.
+fn fake_end(comment_length: u16) -> Vec<u8> {
+    let mut end = 0x06054b50u32.to_le_bytes().to_vec();
+    end.extend_from_slice(&[0, 0, 0, 0, 1, 0, 1, 0]);
+    end.extend_from_slice(&46u32.to_le_bytes());
+    end.extend_from_slice(&0u32.to_le_bytes());
+    end.extend_from_slice(&comment_length.to_le_bytes());
+    end
+}
+
+#[test]
+fn signatures_inside_the_comment_are_skipped() {
+    let comments = [
+        // Complete records that end before the comment does.
+        [b"before ".as_slice(), &fake_end(0), b" after"].concat(),
+        [&fake_end(3)[..], b"abc", &[b'x'; 100]].concat(),
+        // A record whose comment would run past the end of the file.
+        [b"before ".as_slice(), &fake_end(1000), b" after"].concat(),
+        // Only the signature.
+        0x06054b50u32.to_le_bytes().repeat(10),
+    ];
+    for comment in comments {
+        check_entries(&open(common::with_comment(archive(), &comment)).unwrap());
+    }
+}
+
+#[test]
+fn junk_after_the_archive_is_ignored() {
+    for junk in [vec![0; 1], vec![0; 100], b"trailing junk".repeat(1000)] {
+        check_entries(&open([archive(), junk.clone()].concat()).unwrap());
+        check_entries(&open([common::with_comment(archive(), b"comment"), junk].concat()).unwrap());
+    }
+}
+
+#[test]
+fn comments_of_maximum_length_are_read() {
+    for comment_length in [65534, 65535] {
+        let mut comment = vec![b'c'; comment_length];
+        // The start of the comment looks like another record.
+        comment[..22].copy_from_slice(&fake_end(0));
+        let bytes = common::with_comment(archive(), &comment);
+        check_entries(&open(bytes.clone()).unwrap());
+
+        // With a prefix the file is longer than the tail searched for the record.
+        check_entries(&open([vec![0; 100_000], bytes].concat()).unwrap());
+    }
+}
@@ -0,0 +1,74 @@
+#![cfg(feature = "tokio")]
+
+use std::io;
+use std::io::Read;
+use tokio::io::AsyncReadExt;
+use vfs_rs::zip::async_archive::AsyncZipArchive;
+use vfs_rs::zip::codec::Codec;
+use vfs_rs::zip::result::{ZipError, ZipResult};
+use vfs_rs::zip::zip_file::ZipFileData;
+
+mod common;
+
+const INVERTED: u16 = 99;
+
+// Stores every byte inverted, enough to tell a codec apart from stored data.
+struct InvertCodec;
+
+impl Codec for InvertCodec {
+    fn decoder(&self, mut reader: Box<dyn Read + Send>, _data: &ZipFileData) -> io::Result<Box<dyn Read + Send>> {
+        let mut contents = Vec::new();
+        reader.read_to_end(&mut contents)?;
+        contents.iter_mut().for_each(|b| *b = !*b);
+        Ok(Box::new(io::Cursor::new(contents)))
+    }
+}
+
+async fn read(archive: &AsyncZipArchive, name: &str) -> ZipResult<Vec<u8>> {
+    let mut contents = Vec::new();
+    archive.by_name(name).await?.read_to_end(&mut contents).await?;
+    Ok(contents)
+}
+
+#[tokio::test]
+async fn builtin_methods_are_read() {
+    let text = b"deflated text, deflated text, deflated text".repeat(100);
+    let bytes = common::build_archive(
+        &[("stored.txt", common::STORED, b"stored"), ("deflated.txt", common::DEFLATED, &text)],
+        &[],
+        false,
+    )
+    .unwrap();
+    let file = common::TempFile::new("async-builtin.zip", &bytes).unwrap();
+
+    let archive = AsyncZipArchive::new(&file.0).await.unwrap();
+    assert_eq!(read(&archive, "stored.txt").await.unwrap(), b"stored");
+    assert_eq!(read(&archive, "deflated.txt").await.unwrap(), text);
+    assert!(matches!(read(&archive, "missing.txt").await, Err(ZipError::FileNotFound)));
+}
+
+#[tokio::test]
+async fn registered_codecs_are_used() {
+    let inverted: Vec<u8> = b"through a codec".iter().map(|b| !b).collect();
+    let bytes = common::build_archive(&[("custom.bin", INVERTED, &inverted)], &[], false).unwrap();
+    let file = common::TempFile::new("async-codec.zip", &bytes).unwrap();
+
+    let mut archive = AsyncZipArchive::new(&file.0).await.unwrap();
+    assert!(matches!(read(&archive, "custom.bin").await, Err(ZipError::UnsupportedEntry(_, INVERTED))));
+    archive.codecs.register(INVERTED, InvertCodec);
+    assert_eq!(read(&archive, "custom.bin").await.unwrap(), b"through a codec");
+}
+
+#[cfg(feature = "zstd")]
+#[tokio::test]
+async fn feature_codecs_are_used() {
+    let contents = b"zstd compressed contents ".repeat(50);
+    let compressed = zstd::encode_all(&contents[..], 3).unwrap();
+    let bytes = common::build_archive(&[("zstd.bin", 93, &compressed)], &[], false).unwrap();
+    // The builder records the size of what it was given, the entry holds more once decompressed.
+    let bytes = common::set_uncompressed_size(bytes, contents.len() as u32);
+    let file = common::TempFile::new("async-zstd.zip", &bytes).unwrap();
+
+    let archive = AsyncZipArchive::new(&file.0).await.unwrap();
+    assert_eq!(read(&archive, "zstd.bin").await.unwrap(), contents);
+}
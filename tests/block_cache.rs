@@ -0,0 +1,114 @@
+use std::io;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use vfs_rs::zip::block_cache::{BlockCacheOptions, CacheStats, CachedStorage};
+use vfs_rs::zip::storage::Storage;
+use vfs_rs::zip::zip_archive::{ZipArchive, ZipArchiveOptions};
+
+mod common;
+
+struct CountingStorage {
+    bytes: Vec<u8>,
+    reads: AtomicU64,
+}
+
+impl CountingStorage {
+    fn new(bytes: Vec<u8>) -> Arc<Self> {
+        Arc::new(Self { bytes, reads: AtomicU64::new(0) })
+    }
+
+    fn reads(&self) -> u64 {
+        self.reads.load(Ordering::Relaxed)
+    }
+}
+
+impl Storage for CountingStorage {
+    fn len(&self) -> io::Result<u64> {
+        Storage::len(&self.bytes)
+    }
+
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        self.reads.fetch_add(1, Ordering::Relaxed);
+        self.bytes.read_at(buf, offset)
+    }
+}
+
+fn bytes(len: usize) -> Vec<u8> {
+    (0..len).map(|i| (i % 251) as u8).collect()
+}
+
+fn stats(hits: u64, misses: u64) -> CacheStats {
+    CacheStats { hits, misses }
+}
+
+#[test]
+fn small_reads_are_served_from_blocks() {
+    let storage = CountingStorage::new(bytes(10_000));
+    let cache = CachedStorage::new(storage.clone(), BlockCacheOptions { block_size: 1024, capacity: 2 });
+    let mut buf = [0u8; 16];
+
+    assert_eq!(cache.read_at(&mut buf, 100).unwrap(), 16);
+    assert_eq!(buf[..], bytes(10_000)[100..116]);
+    assert_eq!((cache.stats(), storage.reads()), (stats(0, 1), 1));
+    cache.read_exact_at(&mut buf, 500).unwrap();
+    assert_eq!((cache.stats(), storage.reads()), (stats(1, 1), 1));
+
+    // A read is not served across a block boundary.
+    assert_eq!(cache.read_at(&mut buf, 1020).unwrap(), 4);
+    cache.read_exact_at(&mut buf, 1020).unwrap();
+    assert_eq!(buf[..], bytes(10_000)[1020..1036]);
+    assert_eq!(cache.stats(), stats(3, 2));
+
+    // Two blocks fit, the least recently used one goes first.
+    cache.read_exact_at(&mut buf, 2048).unwrap();
+    cache.read_exact_at(&mut buf, 1024).unwrap();
+    assert_eq!(cache.stats(), stats(4, 3));
+    cache.read_exact_at(&mut buf, 0).unwrap();
+    assert_eq!(cache.stats(), stats(4, 4));
+}
+
+#[test]
+fn large_reads_bypass_the_cache() {
+    let storage = CountingStorage::new(bytes(10_000));
+    let cache = CachedStorage::new(storage.clone(), BlockCacheOptions { block_size: 1024, capacity: 2 });
+    let mut small = [0u8; 16];
+    cache.read_exact_at(&mut small, 0).unwrap();
+
+    let mut large = vec![0u8; 5000];
+    assert_eq!(cache.read_at(&mut large, 10).unwrap(), 5000);
+    assert_eq!(large, bytes(10_000)[10..5010]);
+    assert_eq!((cache.stats(), storage.reads()), (stats(0, 1), 2));
+
+    // The cached block survived the large read.
+    cache.read_exact_at(&mut small, 16).unwrap();
+    assert_eq!((cache.stats(), storage.reads()), (stats(1, 1), 2));
+}
+
+#[test]
+fn archive_reports_cache_stats() {
+    let contents = bytes(100_000);
+    let archive = common::build_archive(
+        &[("small.txt", common::STORED, b"small"), ("large.bin", common::STORED, &contents)],
+        &[],
+        false,
+    )
+    .unwrap();
+    let storage = CountingStorage::new(archive);
+    let options = ZipArchiveOptions {
+        block_cache: Some(BlockCacheOptions { block_size: 4096, capacity: 4 }),
+        ..ZipArchiveOptions::default()
+    };
+    let archive = ZipArchive::from_storage(storage.clone(), options).unwrap();
+
+    archive.read_to_vec("small.txt").unwrap();
+    let after_small = archive.cache_stats().unwrap();
+    archive.read_to_vec("small.txt").unwrap();
+    let after_again = archive.cache_stats().unwrap();
+    assert_eq!(after_again.misses, after_small.misses);
+    assert!(after_again.hits > after_small.hits);
+
+    // The whole entry comes from the storage in a single read.
+    let reads = storage.reads();
+    assert_eq!(archive.read_to_vec("large.bin").unwrap(), contents);
+    assert!(storage.reads() - reads <= 2, "{} reads", storage.reads() - reads);
+}
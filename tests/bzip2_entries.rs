@@ -0,0 +1,48 @@
+#![cfg(feature = "bzip2")]
+
+use std::io::{Read, Write};
+use std::sync::Arc;
+use bzip2::Compression;
+use bzip2::write::BzEncoder;
+use vfs_rs::zip::zip_archive::{ZipArchive, ZipArchiveOptions};
+
+mod common;
+
+const BZIP2: u16 = 12;
+
+fn contents() -> Vec<u8> {
+    b"bzip2 compressed contents ".repeat(500)
+}
+
+fn compress(level: u32) -> Vec<u8> {
+    let mut encoder = BzEncoder::new(Vec::new(), Compression::new(level));
+    encoder.write_all(&contents()).unwrap();
+    encoder.finish().unwrap()
+}
+
+fn open(compressed: &[u8]) -> ZipArchive {
+    let bytes = common::build_archive(&[("bzip2.bin", BZIP2, compressed)], &[], false).unwrap();
+    // The builder records the size of what it was given, the entry holds more once decompressed.
+    let bytes = common::set_uncompressed_size(bytes, contents().len() as u32);
+    ZipArchive::from_storage(Arc::new(bytes), ZipArchiveOptions::default()).unwrap()
+}
+
+#[test]
+fn bzip2_entries_are_decompressed() {
+    for level in [1, 9] {
+        let archive = open(&compress(level));
+        assert!(archive.is_supported(&archive.entries["bzip2.bin"]));
+
+        let mut streamed = Vec::new();
+        archive.by_name("bzip2.bin").unwrap().read_to_end(&mut streamed).unwrap();
+        assert_eq!(streamed, contents());
+        assert_eq!(archive.read_to_vec("bzip2.bin").unwrap(), contents());
+    }
+}
+
+#[test]
+fn corrupt_bzip2_entries_fail_to_read() {
+    let mut compressed = compress(6);
+    compressed[0] ^= 0xFF;
+    assert!(open(&compressed).read_to_vec("bzip2.bin").is_err());
+}
@@ -0,0 +1,109 @@
+use std::io;
+use std::io::{Read, Write};
+use vfs_rs::zip::codec::{Codec, CodecRegistry};
+use vfs_rs::zip::result::ZipError;
+use vfs_rs::zip::spec::CompressionMethod;
+use vfs_rs::zip::zip_archive::ZipArchive;
+use vfs_rs::zip::zip_file::ZipFileData;
+use vfs_rs::zip::zip_writer::{FileOptions, ZipWriter, ZipWriterOptions};
+
+mod common;
+
+const INVERTED: u16 = 99;
+
+// Stores every byte inverted, enough to tell a codec apart from stored data.
+struct InvertCodec;
+
+struct InvertWriter(Box<dyn Write + Send>);
+
+impl Write for InvertWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let inverted: Vec<u8> = buf.iter().map(|b| !b).collect();
+        self.0.write_all(&inverted)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+}
+
+impl Codec for InvertCodec {
+    fn decoder(&self, mut reader: Box<dyn Read + Send>, _data: &ZipFileData) -> io::Result<Box<dyn Read + Send>> {
+        let mut contents = Vec::new();
+        reader.read_to_end(&mut contents)?;
+        contents.iter_mut().for_each(|b| *b = !*b);
+        Ok(Box::new(io::Cursor::new(contents)))
+    }
+
+    fn encoder(&self, writer: Box<dyn Write + Send>) -> io::Result<Box<dyn Write + Send>> {
+        Ok(Box::new(InvertWriter(writer)))
+    }
+}
+
+fn registry() -> CodecRegistry {
+    let mut codecs = CodecRegistry::new();
+    codecs.register(INVERTED, InvertCodec);
+    codecs
+}
+
+fn inverted(contents: &[u8]) -> Vec<u8> {
+    contents.iter().map(|b| !b).collect()
+}
+
+#[test]
+fn registered_codecs_decode_entries() {
+    let bytes = common::build_archive(
+        &[("custom.bin", INVERTED, &inverted(b"through a codec")), ("plain.txt", common::DEFLATED, b"plain")],
+        &[],
+        false,
+    )
+    .unwrap();
+    let file = common::TempFile::new("codec-registry.zip", &bytes).unwrap();
+
+    let archive = ZipArchive::with_codecs(&file.0, registry()).unwrap();
+    let mut contents = Vec::new();
+    archive.by_name("custom.bin").unwrap().read_to_end(&mut contents).unwrap();
+    assert_eq!(contents, b"through a codec");
+    assert_eq!(archive.read_to_vec("custom.bin").unwrap(), b"through a codec");
+    assert_eq!(&*archive.read_shared("custom.bin").unwrap(), b"through a codec");
+    assert_eq!(archive.read_to_vec("plain.txt").unwrap(), b"plain");
+}
+
+#[test]
+fn registered_codecs_replace_builtin_methods() {
+    let bytes = common::build_archive(&[("stored.bin", common::STORED, &inverted(b"not stored"))], &[], false).unwrap();
+    let file = common::TempFile::new("codec-override.zip", &bytes).unwrap();
+
+    let mut codecs = registry();
+    codecs.register(common::STORED, InvertCodec);
+    let mut archive = ZipArchive::with_codecs(&file.0, codecs).unwrap();
+    assert_eq!(archive.read_to_vec("stored.bin").unwrap(), b"not stored");
+
+    assert!(archive.codecs.unregister(common::STORED));
+    assert!(!archive.codecs.unregister(common::STORED));
+    assert_eq!(archive.read_to_vec("stored.bin").unwrap(), inverted(b"not stored"));
+}
+
+#[test]
+fn registered_codecs_encode_entries() {
+    let dir = common::TempDir::new("codec-writer").unwrap();
+    let path = dir.0.join("archive.zip");
+    let custom = FileOptions { compression_method: CompressionMethod::Unsupported(INVERTED), ..FileOptions::default() };
+
+    let mut writer = ZipWriter::create(&path, ZipWriterOptions::default()).unwrap();
+    let result = writer.start_file("custom.bin", custom.clone());
+    assert!(matches!(result, Err(ZipError::UnsupportedCompressionMethod(INVERTED))));
+    drop(writer);
+
+    let options = ZipWriterOptions { codecs: registry(), ..ZipWriterOptions::default() };
+    let mut writer = ZipWriter::create(&path, options).unwrap();
+    writer.start_file("custom.bin", custom).unwrap();
+    writer.write_all(b"written through a codec").unwrap();
+    writer.finish().unwrap();
+
+    let archive = ZipArchive::new(&path).unwrap();
+    assert!(matches!(archive.read_to_vec("custom.bin"), Err(ZipError::UnsupportedEntry(_, INVERTED))));
+    let archive = ZipArchive::with_codecs(&path, registry()).unwrap();
+    assert_eq!(archive.read_to_vec("custom.bin").unwrap(), b"written through a codec");
+}
@@ -0,0 +1,205 @@
+// Shared by several test crates, each of which only uses part of it.
+#![allow(dead_code)]
+
+use std::io;
+use std::io::Write;
+use flate2::write::DeflateEncoder;
+use flate2::{Compression, Crc};
+
+pub const STORED: u16 = 0;
+pub const DEFLATED: u16 = 8;
+pub const UTF8_FLAG: u16 = 1 << 11;
+
+// An entry with everything `build_raw_archive` lets a test choose.
+pub struct RawEntry<'a> {
+    pub name: &'a [u8],
+    pub flags: u16,
+    pub method: u16,
+    // Unix mode, file type included.
+    pub mode: u32,
+    pub extra_field: &'a [u8],
+    pub contents: &'a [u8],
+}
+
+impl<'a> RawEntry<'a> {
+    pub fn new(name: &'a [u8], contents: &'a [u8]) -> Self {
+        Self { name, flags: 0, method: STORED, mode: 0o100644, extra_field: &[], contents }
+    }
+}
+
+// Builds an archive of `entries` behind `prefix`. Offsets are recorded relative to the start of the
+// archive unless `adjust_offsets` is set, in which case they include the prefix like `zip -A` does.
+pub fn build_archive(entries: &[(&str, u16, &[u8])], prefix: &[u8], adjust_offsets: bool) -> io::Result<Vec<u8>> {
+    let entries: Vec<_> = entries
+        .iter()
+        .map(|(name, method, contents)| RawEntry { method: *method, ..RawEntry::new(name.as_bytes(), contents) })
+        .collect();
+    build(&entries, prefix, adjust_offsets)
+}
+
+// Builds an archive of stored entries with the given unix modes, file type included.
+pub fn build_unix_archive(entries: &[(&str, u32, &[u8])]) -> io::Result<Vec<u8>> {
+    let entries: Vec<_> = entries
+        .iter()
+        .map(|(name, mode, contents)| RawEntry { mode: *mode, ..RawEntry::new(name.as_bytes(), contents) })
+        .collect();
+    build(&entries, &[], false)
+}
+
+// Builds an archive of entries whose raw names, flags and extra fields are taken as they are.
+pub fn build_raw_archive(entries: &[RawEntry]) -> io::Result<Vec<u8>> {
+    build(entries, &[], false)
+}
+
+fn build(entries: &[RawEntry], prefix: &[u8], adjust_offsets: bool) -> io::Result<Vec<u8>> {
+    let base = if adjust_offsets { 0 } else { prefix.len() as u32 };
+    let mut out = prefix.to_vec();
+    let mut directory = Vec::new();
+
+    for RawEntry { name, flags, method, mode, extra_field, contents } in entries {
+        let compressed = match *method {
+            DEFLATED => {
+                let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+                encoder.write_all(contents)?;
+                encoder.finish()?
+            }
+            _ => contents.to_vec(),
+        };
+        let mut crc = Crc::new();
+        crc.update(contents);
+
+        let mut common = Vec::new();
+        common.extend_from_slice(&[20, 0]);
+        common.extend_from_slice(&flags.to_le_bytes());
+        common.extend_from_slice(&method.to_le_bytes());
+        common.extend_from_slice(&[0, 0, 0x21, 0]);
+        common.extend_from_slice(&crc.sum().to_le_bytes());
+        common.extend_from_slice(&(compressed.len() as u32).to_le_bytes());
+        common.extend_from_slice(&(contents.len() as u32).to_le_bytes());
+        common.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        common.extend_from_slice(&(extra_field.len() as u16).to_le_bytes());
+
+        let header_start = out.len() as u32 - base;
+        out.extend_from_slice(&0x04034b50u32.to_le_bytes());
+        out.extend_from_slice(&common);
+        out.extend_from_slice(name);
+        out.extend_from_slice(extra_field);
+        out.extend_from_slice(&compressed);
+
+        directory.extend_from_slice(&0x02014b50u32.to_le_bytes());
+        directory.extend_from_slice(&[20, 3]);
+        directory.extend_from_slice(&common);
+        directory.extend_from_slice(&[0; 6]);
+        directory.extend_from_slice(&(mode << 16).to_le_bytes());
+        directory.extend_from_slice(&header_start.to_le_bytes());
+        directory.extend_from_slice(name);
+        directory.extend_from_slice(extra_field);
+    }
+
+    let directory_start = out.len() as u32 - base;
+    out.extend_from_slice(&directory);
+    out.extend_from_slice(&0x06054b50u32.to_le_bytes());
+    out.extend_from_slice(&[0; 4]);
+    out.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+    out.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+    out.extend_from_slice(&(directory.len() as u32).to_le_bytes());
+    out.extend_from_slice(&directory_start.to_le_bytes());
+    out.extend_from_slice(&0u16.to_le_bytes());
+    Ok(out)
+}
+
+// Replaces the end of central directory record of an archive from `build_archive` with zip64 end
+// records, followed by a regular record that defers to them and carries `comment`.
+pub fn with_zip64_end(mut archive: Vec<u8>, comment: &[u8]) -> Vec<u8> {
+    let end = archive.split_off(archive.len() - 22);
+    let files = u16::from_le_bytes([end[10], end[11]]) as u64;
+    let directory_size = u32::from_le_bytes(end[12..16].try_into().unwrap()) as u64;
+    let directory_offset = u32::from_le_bytes(end[16..20].try_into().unwrap()) as u64;
+    let zip64_end_offset = archive.len() as u64;
+    archive.extend_from_slice(&zip64_tail(files, directory_size, directory_offset));
+    // `zip64_tail` ends in a record without a comment that locates its zip64 record at 0.
+    let locator = archive.len() - 22 - 20;
+    archive[locator + 8..locator + 16].copy_from_slice(&zip64_end_offset.to_le_bytes());
+    with_comment(archive, comment)
+}
+
+// Gives an archive whose end of central directory record has no comment yet `comment`.
+pub fn with_comment(mut archive: Vec<u8>, comment: &[u8]) -> Vec<u8> {
+    let comment_length = archive.len() - 2;
+    archive[comment_length..].copy_from_slice(&(comment.len() as u16).to_le_bytes());
+    archive.extend_from_slice(comment);
+    archive
+}
+
+// Rewrites the uncompressed size recorded for every entry, in both the local and central headers.
+pub fn set_uncompressed_size(mut bytes: Vec<u8>, size: u32) -> Vec<u8> {
+    for i in 0..bytes.len() - 4 {
+        let field = match u32::from_le_bytes(bytes[i..i + 4].try_into().unwrap()) {
+            0x04034b50 => i + 22,
+            0x02014b50 => i + 24,
+            _ => continue,
+        };
+        bytes[field..field + 4].copy_from_slice(&size.to_le_bytes());
+    }
+    bytes
+}
+
+// An archive that is nothing but zip64 end records, claiming `number_of_files` entries in a
+// central directory of `directory_size` bytes at `directory_offset`.
+pub fn zip64_tail(number_of_files: u64, directory_size: u64, directory_offset: u64) -> Vec<u8> {
+    let mut out = Vec::new();
+    out.extend_from_slice(&0x06064b50u32.to_le_bytes());
+    out.extend_from_slice(&44u64.to_le_bytes());
+    out.extend_from_slice(&[45, 0, 45, 0]);
+    out.extend_from_slice(&[0; 8]);
+    out.extend_from_slice(&number_of_files.to_le_bytes());
+    out.extend_from_slice(&number_of_files.to_le_bytes());
+    out.extend_from_slice(&directory_size.to_le_bytes());
+    out.extend_from_slice(&directory_offset.to_le_bytes());
+
+    out.extend_from_slice(&0x07064b50u32.to_le_bytes());
+    out.extend_from_slice(&0u32.to_le_bytes());
+    out.extend_from_slice(&0u64.to_le_bytes());
+    out.extend_from_slice(&1u32.to_le_bytes());
+
+    out.extend_from_slice(&0x06054b50u32.to_le_bytes());
+    out.extend_from_slice(&[0; 4]);
+    out.extend_from_slice(&[0xFF; 12]);
+    out.extend_from_slice(&0u16.to_le_bytes());
+    out
+}
+
+// Writes `contents` to a file in the temporary directory that is removed again on drop.
+pub struct TempFile(pub std::path::PathBuf);
+
+impl TempFile {
+    pub fn new(name: &str, contents: &[u8]) -> io::Result<Self> {
+        let path = std::env::temp_dir().join(format!("vfs-rs-{}-{}", std::process::id(), name));
+        std::fs::write(&path, contents)?;
+        Ok(Self(path))
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+// An empty directory in the temporary directory that is removed again, with its contents, on drop.
+pub struct TempDir(pub std::path::PathBuf);
+
+impl TempDir {
+    pub fn new(name: &str) -> io::Result<Self> {
+        let path = std::env::temp_dir().join(format!("vfs-rs-{}-{}", std::process::id(), name));
+        let _ = std::fs::remove_dir_all(&path);
+        std::fs::create_dir(&path)?;
+        Ok(Self(path))
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
@@ -0,0 +1,112 @@
+#![cfg(feature = "deflate64")]
+
+use std::io::Read;
+use std::sync::Arc;
+use vfs_rs::zip::zip_archive::{ZipArchive, ZipArchiveOptions};
+
+mod common;
+
+const DEFLATE64: u16 = 9;
+const PREFIX_LEN: usize = 40_000;
+const MATCH_LEN: usize = 100;
+
+// Deterministic bytes that do not repeat within the test data.
+fn noise(len: usize) -> Vec<u8> {
+    let mut state = 0x2545_f491_u32;
+    (0..len)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state as u8
+        })
+        .collect()
+}
+
+fn contents() -> Vec<u8> {
+    let prefix = noise(PREFIX_LEN);
+    [&prefix[..], &prefix[..MATCH_LEN]].concat()
+}
+
+#[derive(Default)]
+struct BitWriter {
+    out: Vec<u8>,
+    bits: u64,
+    count: u32,
+}
+
+impl BitWriter {
+    // Writes `value` least significant bit first, like deflate header fields and extra bits.
+    fn bits(&mut self, value: u64, count: u32) {
+        self.bits |= value << self.count;
+        self.count += count;
+        while self.count >= 8 {
+            self.out.push(self.bits as u8);
+            self.bits >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    // Writes a Huffman code, which deflate packs most significant bit first.
+    fn code(&mut self, code: u64, count: u32) {
+        let reversed = (0..count).fold(0, |reversed, bit| reversed << 1 | (code >> bit & 1));
+        self.bits(reversed, count);
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.count > 0 {
+            self.bits(0, 8 - self.count);
+        }
+        self.out
+    }
+}
+
+// A stored block with the noise, then a fixed Huffman block copying its start back from a
+// distance that only deflate64 can express.
+fn compress() -> Vec<u8> {
+    let prefix = noise(PREFIX_LEN);
+    let mut writer = BitWriter::default();
+    writer.bits(0b000, 3);
+    let mut out = writer.finish();
+    out.extend_from_slice(&(PREFIX_LEN as u16).to_le_bytes());
+    out.extend_from_slice(&(!(PREFIX_LEN as u16)).to_le_bytes());
+    out.extend_from_slice(&prefix);
+
+    let mut writer = BitWriter::default();
+    writer.bits(0b011, 3);
+    // Length code 279 covers 99 to 114 with 4 extra bits.
+    writer.code(279 - 256, 7);
+    writer.bits((MATCH_LEN - 99) as u64, 4);
+    // Distance code 30 covers 32769 to 49152 with 14 extra bits.
+    writer.code(30, 5);
+    writer.bits((PREFIX_LEN - 32769) as u64, 14);
+    writer.code(0, 7);
+    out.extend_from_slice(&writer.finish());
+    out
+}
+
+fn open(compressed: &[u8]) -> ZipArchive {
+    let bytes = common::build_archive(&[("deflate64.bin", DEFLATE64, compressed)], &[], false).unwrap();
+    // The builder records the size of what it was given, the entry holds more once decompressed.
+    let bytes = common::set_uncompressed_size(bytes, contents().len() as u32);
+    ZipArchive::from_storage(Arc::new(bytes), ZipArchiveOptions::default()).unwrap()
+}
+
+#[test]
+fn deflate64_entries_are_decompressed() {
+    let archive = open(&compress());
+    assert!(archive.is_supported(&archive.entries["deflate64.bin"]));
+
+    let mut streamed = Vec::new();
+    archive.by_name("deflate64.bin").unwrap().read_to_end(&mut streamed).unwrap();
+    assert!(streamed == contents());
+    assert!(archive.read_to_vec("deflate64.bin").unwrap() == contents());
+}
+
+#[test]
+fn corrupt_deflate64_entries_fail_to_read() {
+    let mut writer = BitWriter::default();
+    // A final block of the reserved type 3.
+    writer.bits(0b111, 3);
+    assert!(open(&writer.finish()).read_to_vec("deflate64.bin").is_err());
+}
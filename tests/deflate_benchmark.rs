@@ -0,0 +1,79 @@
+use std::fs::File;
+use std::io;
+use std::io::Read;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Instant;
+use vfs_rs::zip::storage::Storage;
+use vfs_rs::zip::zip_archive::{ZipArchive, ZipArchiveOptions};
+
+mod common;
+
+const ENTRY_NAME: &str = "data.txt";
+const ENTRY_SIZE: usize = 8 * 1024 * 1024;
+
+struct CountingStorage {
+    file: File,
+    reads: AtomicU64,
+}
+
+impl Storage for CountingStorage {
+    fn len(&self) -> io::Result<u64> {
+        self.file.len()
+    }
+
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        self.reads.fetch_add(1, Ordering::Relaxed);
+        self.file.read_at(buf, offset)
+    }
+}
+
+fn contents() -> Vec<u8> {
+    let mut state = 0x2545_f491_u32;
+    let mut data = Vec::with_capacity(ENTRY_SIZE);
+    while data.len() < ENTRY_SIZE {
+        state ^= state << 13;
+        state ^= state >> 17;
+        state ^= state << 5;
+        let word = ["alpha ", "beta ", "gamma ", "delta ", "epsilon\n"][(state % 5) as usize];
+        data.extend_from_slice(&word.as_bytes()[..word.len().min(ENTRY_SIZE - data.len())]);
+    }
+    data
+}
+
+// Returns the storage reads needed to inflate the entry and the throughput in MiB/s.
+fn inflate(path: &std::path::Path, deflate_buffer_size: usize, expected: &[u8]) -> (u64, f64) {
+    let storage = Arc::new(CountingStorage {
+        file: File::open(path).unwrap(),
+        reads: AtomicU64::new(0),
+    });
+    let options = ZipArchiveOptions { deflate_buffer_size, ..Default::default() };
+    let archive = ZipArchive::from_storage(storage.clone(), options).unwrap();
+
+    let reads_before = storage.reads.load(Ordering::Relaxed);
+    let start = Instant::now();
+    let mut contents = Vec::with_capacity(expected.len());
+    archive.by_name(ENTRY_NAME).unwrap().read_to_end(&mut contents).unwrap();
+    let elapsed = start.elapsed();
+    assert!(contents == expected, "inflated contents differ");
+
+    let reads = storage.reads.load(Ordering::Relaxed) - reads_before;
+    (reads, expected.len() as f64 / (1024.0 * 1024.0) / elapsed.as_secs_f64())
+}
+
+#[test]
+fn deflate_input_buffer_throughput() {
+    let contents = contents();
+    let archive = common::build_archive(&[(ENTRY_NAME, common::DEFLATED, &contents)], &[], false).unwrap();
+    let file = common::TempFile::new("deflate-benchmark.zip", &archive).unwrap();
+
+    let (small_reads, small_throughput) = inflate(&file.0, 32, &contents);
+    let (large_reads, large_throughput) = inflate(&file.0, 0, &contents);
+
+    // Throughput depends on the machine, it is only reported on request.
+    if std::env::var_os("VFS_RS_BENCHMARK").is_some() {
+        eprintln!("32 byte buffer: {} reads, {:.1} MiB/s", small_reads, small_throughput);
+        eprintln!("default buffer: {} reads, {:.1} MiB/s", large_reads, large_throughput);
+    }
+    assert!(large_reads * 100 < small_reads);
+}
@@ -0,0 +1,70 @@
+use std::sync::Arc;
+use vfs_rs::zip::block_cache::CacheStats;
+use vfs_rs::zip::entry_cache::EntryCache;
+use vfs_rs::zip::zip_archive::{ZipArchive, ZipArchiveOptions};
+
+mod common;
+
+fn open(entries: &[(&str, u16, &[u8])], cache: &EntryCache) -> ZipArchive {
+    let bytes = common::build_archive(entries, &[], false).unwrap();
+    let options = ZipArchiveOptions { entry_cache: Some(cache.clone()), ..ZipArchiveOptions::default() };
+    ZipArchive::from_storage(Arc::new(bytes), options).unwrap()
+}
+
+#[test]
+fn least_recently_used_entries_are_evicted() {
+    let cache = EntryCache::new(250);
+    let archive = open(
+        &[("a", common::STORED, &[1; 100]), ("b", common::DEFLATED, &[2; 100]), ("c", common::STORED, &[3; 100])],
+        &cache,
+    );
+
+    assert_eq!(archive.read_to_vec("a").unwrap(), [1; 100]);
+    assert_eq!(&*archive.read_shared("b").unwrap(), [2; 100]);
+    assert_eq!((cache.len(), cache.size()), (2, 200));
+    assert_eq!(archive.read_to_vec("a").unwrap(), [1; 100]);
+    assert_eq!(cache.stats(), CacheStats { hits: 1, misses: 2 });
+
+    // `b` was used least recently.
+    assert_eq!(archive.read_to_vec("c").unwrap(), [3; 100]);
+    assert_eq!((cache.len(), cache.size()), (2, 200));
+    archive.read_to_vec("a").unwrap();
+    archive.read_to_vec("b").unwrap();
+    assert_eq!(cache.stats(), CacheStats { hits: 2, misses: 4 });
+}
+
+#[test]
+fn entries_larger_than_the_budget_are_not_cached() {
+    let cache = EntryCache::new(100);
+    let archive = open(&[("large", common::DEFLATED, &[7; 101]), ("small", common::STORED, &[8; 100])], &cache);
+
+    for _ in 0..2 {
+        assert_eq!(archive.read_to_vec("large").unwrap(), [7; 101]);
+        assert_eq!(&*archive.read_shared("large").unwrap(), [7; 101]);
+    }
+    assert!(cache.is_empty());
+    assert_eq!(cache.stats(), CacheStats::default());
+
+    archive.read_to_vec("small").unwrap();
+    assert_eq!((cache.len(), cache.size()), (1, 100));
+}
+
+#[test]
+fn one_cache_serves_several_archives() {
+    let cache = EntryCache::new(1000);
+    let first = open(&[("same.txt", common::STORED, b"first archive")], &cache);
+    let second = open(&[("same.txt", common::DEFLATED, b"second archive")], &cache);
+
+    for _ in 0..2 {
+        assert_eq!(first.read_to_vec("same.txt").unwrap(), b"first archive");
+        assert_eq!(second.read_to_vec("same.txt").unwrap(), b"second archive");
+    }
+    assert_eq!(cache.len(), 2);
+    assert_eq!(cache.stats(), CacheStats { hits: 2, misses: 2 });
+
+    // Dropping an archive frees its entries, the other archive's stay.
+    drop(first);
+    assert_eq!((cache.len(), cache.size()), (1, b"second archive".len()));
+    assert_eq!(second.read_to_vec("same.txt").unwrap(), b"second archive");
+    assert_eq!(cache.stats(), CacheStats { hits: 3, misses: 2 });
+}
@@ -0,0 +1,117 @@
+use std::io::Read;
+use std::sync::Arc;
+use encoding_rs::{GBK, SHIFT_JIS};
+use flate2::Crc;
+use vfs_rs::zip::encoding::NameEncoding;
+use vfs_rs::zip::zip_archive::{ZipArchive, ZipArchiveOptions};
+use common::RawEntry;
+
+mod common;
+
+fn open(entries: &[RawEntry], options: ZipArchiveOptions) -> ZipArchive {
+    ZipArchive::from_storage(Arc::new(common::build_raw_archive(entries).unwrap()), options).unwrap()
+}
+
+fn read(mut file: impl Read) -> Vec<u8> {
+    let mut contents = Vec::new();
+    file.read_to_end(&mut contents).unwrap();
+    contents
+}
+
+#[test]
+fn lossy_collisions_stay_reachable_by_raw_name() {
+    let archive = open(
+        &[
+            RawEntry { flags: common::UTF8_FLAG, ..RawEntry::new(b"\xff", b"first") },
+            RawEntry { flags: common::UTF8_FLAG, ..RawEntry::new(b"\xfe", b"second") },
+            RawEntry::new(b"plain.txt", b"plain"),
+        ],
+        ZipArchiveOptions::default(),
+    );
+    assert_eq!(archive.lossy_collisions(), ["\u{fffd}"]);
+    assert_eq!(read(archive.by_name("\u{fffd}").unwrap()), b"second");
+    assert_eq!(read(archive.by_name_bytes(b"\xff").unwrap()), b"first");
+    assert_eq!(read(archive.by_name_bytes(b"\xfe").unwrap()), b"second");
+    assert_eq!(read(archive.by_name_bytes(b"plain.txt").unwrap()), b"plain");
+    assert!(archive.by_name_bytes(b"\xfd").is_err());
+}
+
+// An Info-ZIP Unicode path extra field for a header name of `original`.
+fn unicode_path(original: &[u8], name: &str, matching_crc: bool) -> Vec<u8> {
+    let mut crc = Crc::new();
+    crc.update(original);
+    let crc = if matching_crc { crc.sum() } else { !crc.sum() };
+    let mut field = Vec::new();
+    field.extend_from_slice(&0x7075u16.to_le_bytes());
+    field.extend_from_slice(&(5 + name.len() as u16).to_le_bytes());
+    field.push(1);
+    field.extend_from_slice(&crc.to_le_bytes());
+    field.extend_from_slice(name.as_bytes());
+    field
+}
+
+#[test]
+fn unicode_path_field_is_preferred() {
+    let current = unicode_path(b"caf\x82.txt", "caf\u{e9} \u{2615}.txt", true);
+    let archive = open(
+        &[RawEntry { extra_field: &current, ..RawEntry::new(b"caf\x82.txt", b"coffee") }],
+        ZipArchiveOptions::default(),
+    );
+    assert_eq!(read(archive.by_name("caf\u{e9} \u{2615}.txt").unwrap()), b"coffee");
+    assert_eq!(read(archive.by_name_bytes(b"caf\x82.txt").unwrap()), b"coffee");
+}
+
+#[test]
+fn stale_unicode_path_field_is_ignored() {
+    // The header name was changed after the field was written, its CRC no longer matches.
+    let stale = unicode_path(b"caf\x82.txt", "old name.txt", false);
+    let archive = open(
+        &[RawEntry { extra_field: &stale, ..RawEntry::new(b"caf\x82.txt", b"coffee") }],
+        ZipArchiveOptions::default(),
+    );
+    assert!(archive.by_name("old name.txt").is_err());
+    assert_eq!(read(archive.by_name("caf\u{e9}.txt").unwrap()), b"coffee");
+}
+
+fn open_auto(names: &[&[u8]]) -> ZipArchive {
+    let entries: Vec<RawEntry> = names.iter().map(|name| RawEntry::new(name, b"contents")).collect();
+    open(&entries, ZipArchiveOptions { name_encoding: NameEncoding::Auto, ..ZipArchiveOptions::default() })
+}
+
+#[test]
+fn auto_detects_gbk() {
+    let names = ["\u{4e2d}\u{6587}\u{6587}\u{4ef6}.txt", "\u{6d4b}\u{8bd5}/\u{62a5}\u{544a}.doc"];
+    let encoded: Vec<Vec<u8>> = names.iter().map(|name| GBK.encode(name).0.into_owned()).collect();
+    let archive = open_auto(&encoded.iter().map(Vec::as_slice).collect::<Vec<_>>());
+    assert_eq!(archive.name_encoding, NameEncoding::Gbk);
+    for name in names {
+        assert!(archive.by_name(name).is_ok(), "{} not found", name);
+    }
+}
+
+#[test]
+fn auto_detects_shift_jis() {
+    let names = ["\u{65e5}\u{672c}\u{8a9e}\u{306e}\u{30d5}\u{30a1}\u{30a4}\u{30eb}.txt", "\u{3066}\u{3059}\u{3068}.csv"];
+    let encoded: Vec<Vec<u8>> = names.iter().map(|name| SHIFT_JIS.encode(name).0.into_owned()).collect();
+    let archive = open_auto(&encoded.iter().map(Vec::as_slice).collect::<Vec<_>>());
+    assert_eq!(archive.name_encoding, NameEncoding::ShiftJis);
+    for name in names {
+        assert!(archive.by_name(name).is_ok(), "{} not found", name);
+    }
+}
+
+#[test]
+fn auto_detects_cp437() {
+    // `Größe.txt` and `Über.txt`, the first also decodes without errors as Shift-JIS and GBK.
+    let archive = open_auto(&[b"Gr\x94\xe1e.txt", b"\x9aber.txt"]);
+    assert_eq!(archive.name_encoding, NameEncoding::Cp437);
+    assert!(archive.by_name("Gr\u{f6}\u{df}e.txt").is_ok());
+    assert!(archive.by_name("\u{dc}ber.txt").is_ok());
+}
+
+#[test]
+fn auto_keeps_utf8_names() {
+    let archive = open_auto(&["na\u{ef}ve.txt".as_bytes()]);
+    assert_eq!(archive.name_encoding, NameEncoding::Utf8);
+    assert!(archive.by_name("na\u{ef}ve.txt").is_ok());
+}
@@ -0,0 +1,216 @@
+use std::fs;
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+use vfs_rs::zip::extract::{ExtractOptions, ExtractProgress, ParallelExtractOptions};
+use vfs_rs::zip::result::ZipError;
+use vfs_rs::zip::zip_archive::{ZipArchive, ZipArchiveOptions};
+use vfs_rs::zip::zip_writer::{FileOptions, ZipWriter, ZipWriterOptions};
+
+mod common;
+
+#[cfg(unix)]
+const FILE: u32 = 0o100644;
+#[cfg(unix)]
+const SYMLINK: u32 = 0o120777;
+
+fn open(bytes: Vec<u8>) -> ZipArchive {
+    ZipArchive::from_storage(Arc::new(bytes), ZipArchiveOptions::default()).unwrap()
+}
+
+#[test]
+fn unsafe_names_are_refused() {
+    for name in ["../evil.txt", "a/../../evil.txt", "/evil.txt", "\\evil.txt", "C:/evil.txt", "c:evil.txt", "evil\0.txt"] {
+        let dir = common::TempDir::new("extract-unsafe").unwrap();
+        let archive = open(common::build_archive(&[(name, common::STORED, b"evil")], &[], false).unwrap());
+        let result = archive.extract_to(dir.0.join("out"));
+        assert!(matches!(result, Err(ZipError::UnsafePath(_))), "{:?} was extracted", name);
+        assert!(!dir.0.join("evil.txt").exists());
+    }
+}
+
+#[test]
+fn entries_are_extracted() {
+    let dir = common::TempDir::new("extract-entries").unwrap();
+    let archive = open(
+        common::build_archive(
+            &[("a.txt", common::STORED, b"alpha"), ("sub/dir/b.txt", common::DEFLATED, b"beta beta beta")],
+            &[],
+            false,
+        )
+        .unwrap(),
+    );
+    archive.extract_to(&dir.0).unwrap();
+    assert_eq!(fs::read(dir.0.join("a.txt")).unwrap(), b"alpha");
+    assert_eq!(fs::read(dir.0.join("sub/dir/b.txt")).unwrap(), b"beta beta beta");
+}
+
+#[cfg(unix)]
+#[test]
+fn enclosed_symlinks_are_created() {
+    let dir = common::TempDir::new("extract-symlink").unwrap();
+    let archive = open(
+        common::build_unix_archive(&[("notes.txt", FILE, b"notes"), ("docs/readme", SYMLINK, b"../notes.txt")]).unwrap(),
+    );
+    archive.extract_to(&dir.0).unwrap();
+    assert!(fs::symlink_metadata(dir.0.join("docs/readme")).unwrap().file_type().is_symlink());
+    assert_eq!(fs::read(dir.0.join("docs/readme")).unwrap(), b"notes");
+}
+
+#[cfg(unix)]
+#[test]
+fn escaping_symlinks_are_refused() {
+    let links: [&[(&str, u32, &[u8])]; 5] = [
+        &[("link", SYMLINK, b"..")],
+        &[("sub/link", SYMLINK, b"../../outside")],
+        &[("link", SYMLINK, b"/etc/passwd")],
+        // Each link is enclosed on its own, but `e` resolves through `c` to the parent of the root.
+        &[("c", SYMLINK, b"."), ("e", SYMLINK, b"c/..")],
+        &[("a", SYMLINK, b"c/.."), ("c", SYMLINK, b".")],
+    ];
+    for entries in links {
+        let dir = common::TempDir::new("extract-escape").unwrap();
+        let root = dir.0.join("out");
+        let result = open(common::build_unix_archive(entries).unwrap()).extract_to(&root);
+        assert!(matches!(result, Err(ZipError::UnsafePath(_))), "{:?} was extracted", entries);
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn files_are_not_written_through_existing_symlinks() {
+    let dir = common::TempDir::new("extract-existing-link").unwrap();
+    let root = dir.0.join("out");
+    fs::create_dir(&root).unwrap();
+    std::os::unix::fs::symlink(&dir.0, root.join("up")).unwrap();
+    let archive = open(common::build_archive(&[("up/evil.txt", common::STORED, b"evil")], &[], false).unwrap());
+    assert!(matches!(archive.extract_to(&root), Err(ZipError::UnsafePath(_))));
+    assert!(!dir.0.join("evil.txt").exists());
+}
+
+fn write_archive(path: &std::path::Path, unix_mode: u32) {
+    // 2020-05-17 12:30:20 and 2019-01-02 03:04:06.
+    let file = FileOptions {
+        last_mod_date: (40 << 9) | (5 << 5) | 17,
+        last_mod_time: (12 << 11) | (30 << 5) | 10,
+        unix_mode: Some(unix_mode),
+        ..FileOptions::default()
+    };
+    let directory = FileOptions {
+        last_mod_date: (39 << 9) | (1 << 5) | 2,
+        last_mod_time: (3 << 11) | (4 << 5) | 3,
+        unix_mode: Some(0o750),
+        ..FileOptions::default()
+    };
+    let mut writer = ZipWriter::create(path, ZipWriterOptions::default()).unwrap();
+    writer.add_directory("dir", directory).unwrap();
+    writer.start_file("dir/run.sh", file).unwrap();
+    writer.write_all(b"#!/bin/sh\n").unwrap();
+    writer.finish().unwrap();
+}
+
+#[test]
+fn timestamps_are_restored() {
+    let dir = common::TempDir::new("extract-times").unwrap();
+    write_archive(&dir.0.join("times.zip"), 0o755);
+    let root = dir.0.join("out");
+    ZipArchive::new(dir.0.join("times.zip")).unwrap().extract_to(&root).unwrap();
+
+    let modified = |path: &str| fs::metadata(root.join(path)).unwrap().modified().unwrap();
+    assert_eq!(modified("dir/run.sh"), SystemTime::UNIX_EPOCH + Duration::from_secs(1589718620));
+    assert_eq!(modified("dir"), SystemTime::UNIX_EPOCH + Duration::from_secs(1546398246));
+}
+
+#[cfg(unix)]
+#[test]
+fn modes_are_restored_without_special_bits() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = common::TempDir::new("extract-modes").unwrap();
+    write_archive(&dir.0.join("modes.zip"), 0o4755);
+    let archive = ZipArchive::new(dir.0.join("modes.zip")).unwrap();
+    let mode = |path: std::path::PathBuf| fs::metadata(path).unwrap().permissions().mode() & 0o7777;
+
+    let root = dir.0.join("plain");
+    archive.extract_to(&root).unwrap();
+    assert_eq!(mode(root.join("dir/run.sh")), 0o755);
+    assert_eq!(mode(root.join("dir")), 0o750);
+
+    let root = dir.0.join("special");
+    archive
+        .extract_to_with_options(&root, ExtractOptions { keep_special_bits: true })
+        .unwrap();
+    assert_eq!(mode(root.join("dir/run.sh")), 0o4755);
+}
+
+fn many_files(count: usize) -> ZipArchive {
+    let names: Vec<String> = (0..count).map(|i| format!("files/{:03}.txt", i)).collect();
+    let contents: Vec<Vec<u8>> = (0..count).map(|i| vec![b'x'; i + 1]).collect();
+    let entries: Vec<(&str, u16, &[u8])> = names
+        .iter()
+        .zip(&contents)
+        .map(|(name, contents)| (name.as_str(), common::DEFLATED, contents.as_slice()))
+        .collect();
+    open(common::build_archive(&entries, &[], false).unwrap())
+}
+
+#[test]
+fn parallel_extract_reports_progress() {
+    let dir = common::TempDir::new("extract-parallel").unwrap();
+    let reports = Arc::new(Mutex::new(Vec::new()));
+    let progress = {
+        let reports = reports.clone();
+        Arc::new(move |progress: &ExtractProgress| {
+            reports.lock().unwrap().push((
+                progress.entries_done,
+                progress.entries_total,
+                progress.bytes_done,
+                progress.bytes_total,
+            ));
+        })
+    };
+    let options = ParallelExtractOptions {
+        threads: 4,
+        progress: Some(progress),
+        ..ParallelExtractOptions::default()
+    };
+    many_files(50).extract_to_parallel(&dir.0, options).unwrap();
+
+    for i in 0..50 {
+        assert_eq!(fs::read(dir.0.join(format!("files/{:03}.txt", i))).unwrap(), vec![b'x'; i + 1]);
+    }
+    let mut reports = reports.lock().unwrap().clone();
+    reports.sort();
+    let bytes_total = (1..=50).sum::<u64>();
+    assert_eq!(reports.len(), 50);
+    assert!(reports.iter().enumerate().all(|(i, report)| report.0 == i + 1));
+    assert!(reports.iter().all(|report| report.1 == 50 && report.3 == bytes_total));
+    assert_eq!(reports.iter().map(|report| report.2).max(), Some(bytes_total));
+}
+
+#[test]
+fn parallel_extract_can_be_cancelled() {
+    let dir = common::TempDir::new("extract-cancel-early").unwrap();
+    let options = ParallelExtractOptions {
+        cancel: Some(Arc::new(AtomicBool::new(true))),
+        ..ParallelExtractOptions::default()
+    };
+    assert!(matches!(many_files(10).extract_to_parallel(&dir.0, options), Err(ZipError::Cancelled)));
+    assert!(!dir.0.join("files/000.txt").exists());
+
+    let dir = common::TempDir::new("extract-cancel-late").unwrap();
+    let cancel = Arc::new(AtomicBool::new(false));
+    let options = ParallelExtractOptions {
+        threads: 1,
+        progress: Some({
+            let cancel = cancel.clone();
+            Arc::new(move |_: &ExtractProgress| cancel.store(true, Ordering::Relaxed))
+        }),
+        cancel: Some(cancel),
+        ..ParallelExtractOptions::default()
+    };
+    assert!(matches!(many_files(10).extract_to_parallel(&dir.0, options), Err(ZipError::Cancelled)));
+    assert!(dir.0.join("files/000.txt").exists());
+    assert!(!dir.0.join("files/001.txt").exists());
+}
@@ -0,0 +1,91 @@
+#![cfg(feature = "http")]
+
+use std::io;
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::sync::Arc;
+use std::thread;
+use vfs_rs::zip::result::ZipError;
+use vfs_rs::zip::zip_archive::{ZipArchive, ZipArchiveOptions};
+
+mod common;
+
+// Serves `contents` on a local port until the test process exits. Range requests are answered with
+// the requested bytes unless `ranges` is false, in which case the whole body comes back with a 200
+// like from a server that ignores the header.
+fn serve(contents: Vec<u8>, ranges: bool) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/archive.zip", listener.local_addr().unwrap());
+    let contents = Arc::new(contents);
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(mut stream) = stream else { continue };
+            let contents = contents.clone();
+            thread::spawn(move || {
+                let mut range = None;
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut line = String::new();
+                while reader.read_line(&mut line).is_ok_and(|size| size > 2) {
+                    let header = line.to_ascii_lowercase();
+                    if let Some(value) = header.strip_prefix("range: bytes=") {
+                        let (start, end) = value.trim().split_once('-').unwrap();
+                        range = Some((start.parse::<usize>().unwrap(), end.parse::<usize>().unwrap()));
+                    }
+                    line.clear();
+                }
+
+                let response = match range.filter(|_| ranges) {
+                    Some((start, end)) => {
+                        let end = end.min(contents.len() - 1);
+                        let head = format!(
+                            "HTTP/1.1 206 Partial Content\r\nContent-Range: bytes {}-{}/{}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                            start,
+                            end,
+                            contents.len(),
+                            end + 1 - start,
+                        );
+                        [head.as_bytes(), &contents[start..=end]].concat()
+                    }
+                    None => {
+                        let head = format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n", contents.len());
+                        [head.as_bytes(), &contents[..]].concat()
+                    }
+                };
+                let _ = stream.write_all(&response);
+            });
+        }
+    });
+    url
+}
+
+fn large() -> Vec<u8> {
+    (0..200_000u32).flat_map(|i| (i % 251).to_le_bytes()).collect()
+}
+
+fn archive() -> Vec<u8> {
+    let large = large();
+    common::build_archive(
+        &[("small.txt", common::STORED, b"hello over http"), ("large.bin", common::DEFLATED, &large)],
+        &[],
+        false,
+    )
+    .unwrap()
+}
+
+#[test]
+fn archive_is_read_with_range_requests() {
+    let url = serve(archive(), true);
+    let archive = ZipArchive::from_url(&url, ZipArchiveOptions::default()).unwrap();
+    assert_eq!(archive.read_to_vec("small.txt").unwrap(), b"hello over http");
+    assert_eq!(archive.read_to_vec("large.bin").unwrap(), large());
+}
+
+#[test]
+fn server_ignoring_ranges_is_rejected() {
+    let url = serve(archive(), false);
+    match ZipArchive::from_url(&url, ZipArchiveOptions::default()) {
+        Err(ZipError::Io(e)) => assert_eq!(e.kind(), io::ErrorKind::Unsupported),
+        Err(e) => panic!("unexpected error {}", e),
+        Ok(_) => panic!("archive was opened without range support"),
+    }
+}
@@ -0,0 +1,70 @@
+use std::io::Read;
+use std::sync::Arc;
+use vfs_rs::zip::limits::ZipLimits;
+use vfs_rs::zip::result::{ZipError, ZipResult};
+use vfs_rs::zip::zip_archive::{ZipArchive, ZipArchiveOptions};
+
+mod common;
+
+fn open(bytes: Vec<u8>, limits: ZipLimits) -> ZipResult<ZipArchive> {
+    ZipArchive::from_storage(Arc::new(bytes), ZipArchiveOptions { limits, ..ZipArchiveOptions::default() })
+}
+
+fn archive(entries: &[(&str, u16, &[u8])]) -> Vec<u8> {
+    common::build_archive(entries, &[], false).unwrap()
+}
+
+#[test]
+fn too_many_entries() {
+    let bytes = archive(&[("a", common::STORED, b"a"), ("b", common::STORED, b"b"), ("c", common::STORED, b"c")]);
+    let limits = ZipLimits { max_entries: Some(2), ..ZipLimits::default() };
+    assert!(matches!(open(bytes.clone(), limits), Err(ZipError::TooManyEntries(3))));
+    let limits = ZipLimits { max_entries: Some(3), ..ZipLimits::default() };
+    assert!(open(bytes, limits).is_ok());
+}
+
+#[test]
+fn entry_too_large() {
+    let bytes = archive(&[("small", common::STORED, &[0; 100]), ("large", common::DEFLATED, &[0; 1000])]);
+    let limits = ZipLimits { max_entry_size: Some(500), ..ZipLimits::default() };
+    match open(bytes, limits) {
+        Err(ZipError::EntryTooLarge(name, 1000)) => assert_eq!(name, "large"),
+        Err(e) => panic!("unexpected error {}", e),
+        Ok(_) => panic!("archive was opened"),
+    }
+}
+
+#[test]
+fn compression_ratio_too_high() {
+    let bytes = archive(&[("text", common::DEFLATED, b"not very compressible"), ("zeros", common::DEFLATED, &[0; 100_000])]);
+    let limits = ZipLimits { max_ratio: Some(100), ..ZipLimits::default() };
+    match open(bytes, limits) {
+        Err(ZipError::CompressionRatioTooHigh(name)) => assert_eq!(name, "zeros"),
+        Err(e) => panic!("unexpected error {}", e),
+        Ok(_) => panic!("archive was opened"),
+    }
+}
+
+#[test]
+fn total_size_too_large() {
+    let bytes = archive(&[("a", common::STORED, &[1; 400]), ("b", common::STORED, &[2; 400]), ("c", common::STORED, &[3; 400])]);
+    let limits = ZipLimits { max_entry_size: Some(500), max_total_size: Some(1000), ..ZipLimits::default() };
+    assert!(matches!(open(bytes, limits), Err(ZipError::TotalSizeTooLarge(1200))));
+}
+
+#[test]
+fn entry_decompressing_past_its_size_is_stopped() {
+    let contents: Vec<u8> = (0..100_000u32).map(|i| (i % 7) as u8).collect();
+    let bytes = common::set_uncompressed_size(archive(&[("liar", common::DEFLATED, &contents)]), 1000);
+    let limits = ZipLimits { max_entry_size: Some(1 << 20), ..ZipLimits::default() };
+    let archive = open(bytes, limits).unwrap();
+
+    let mut file = archive.by_name("liar").unwrap();
+    let mut out = Vec::new();
+    let error = file.read_to_end(&mut out).unwrap_err();
+    assert!(out.len() <= 1000);
+    let error = error.get_ref().and_then(|e| e.downcast_ref::<ZipError>());
+    assert!(matches!(error, Some(ZipError::EntrySizeExceeded(name)) if name == "liar"));
+
+    assert!(matches!(archive.read_to_vec("liar"), Err(ZipError::EntrySizeExceeded(_))));
+}
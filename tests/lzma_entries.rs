@@ -0,0 +1,56 @@
+#![cfg(feature = "lzma")]
+
+use std::io::{Read, Write};
+use std::sync::Arc;
+use xz2::stream::{LzmaOptions, Stream};
+use xz2::write::XzEncoder;
+use vfs_rs::zip::zip_archive::{ZipArchive, ZipArchiveOptions};
+
+mod common;
+
+const LZMA: u16 = 14;
+
+fn contents() -> Vec<u8> {
+    b"lzma compressed contents ".repeat(500)
+}
+
+// Compresses to the .lzma format and turns its header into the one zip uses: a version and the size
+// of the properties in front of them, with the uncompressed size left to the central directory.
+fn compress(preset: u32) -> Vec<u8> {
+    let stream = Stream::new_lzma_encoder(&LzmaOptions::new_preset(preset).unwrap()).unwrap();
+    let mut encoder = XzEncoder::new_stream(Vec::new(), stream);
+    encoder.write_all(&contents()).unwrap();
+    let lzma = encoder.finish().unwrap();
+    let (properties, data) = (&lzma[..5], &lzma[13..]);
+    [&[9, 20, 5, 0], properties, data].concat()
+}
+
+fn open(compressed: &[u8]) -> ZipArchive {
+    let bytes = common::build_archive(&[("lzma.bin", LZMA, compressed)], &[], false).unwrap();
+    // The builder records the size of what it was given, the entry holds more once decompressed.
+    let bytes = common::set_uncompressed_size(bytes, contents().len() as u32);
+    ZipArchive::from_storage(Arc::new(bytes), ZipArchiveOptions::default()).unwrap()
+}
+
+#[test]
+fn lzma_entries_are_decompressed() {
+    for preset in [1, 9] {
+        let archive = open(&compress(preset));
+        assert!(archive.is_supported(&archive.entries["lzma.bin"]));
+
+        let mut streamed = Vec::new();
+        archive.by_name("lzma.bin").unwrap().read_to_end(&mut streamed).unwrap();
+        assert_eq!(streamed, contents());
+        assert_eq!(archive.read_to_vec("lzma.bin").unwrap(), contents());
+    }
+}
+
+#[test]
+fn invalid_lzma_headers_are_refused() {
+    let mut compressed = compress(6);
+    // Properties claimed to be 6 bytes long.
+    compressed[2] = 6;
+    assert!(open(&compressed).read_to_vec("lzma.bin").is_err());
+    // Not even the header is there.
+    assert!(open(&compressed[..3]).read_to_vec("lzma.bin").is_err());
+}
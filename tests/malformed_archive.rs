@@ -0,0 +1,58 @@
+use std::sync::Arc;
+use vfs_rs::zip::result::ZipError;
+use vfs_rs::zip::storage;
+use vfs_rs::zip::zip_archive::{ZipArchive, ZipArchiveOptions};
+use common::RawEntry;
+
+mod common;
+
+#[test]
+fn read_range_is_bounded_by_storage() {
+    let data = vec![7u8; 100];
+    assert_eq!(storage::read_range(&data, 40, 1 << 40).unwrap(), vec![7u8; 60]);
+    assert!(storage::read_range(&data, 200, 1 << 40).unwrap().is_empty());
+}
+
+#[test]
+fn huge_zip64_directory_size_does_not_allocate() {
+    let file = common::TempFile::new("huge-directory.zip", &common::zip64_tail(1, 1 << 40, 0)).unwrap();
+    assert!(ZipArchive::new(&file.0).is_err());
+}
+
+#[test]
+fn zip64_directory_must_fit_in_the_file() {
+    let file = common::TempFile::new("zip64-past-end.zip", &common::zip64_tail(1, 46, 1 << 20)).unwrap();
+    assert!(matches!(ZipArchive::new(&file.0), Err(ZipError::InvalidArchive(_))));
+}
+
+#[test]
+fn zip64_entry_count_must_fit_in_the_directory() {
+    let file = common::TempFile::new("zip64-many-entries.zip", &common::zip64_tail(1 << 40, 0, 0)).unwrap();
+    assert!(matches!(ZipArchive::new(&file.0), Err(ZipError::InvalidArchive(_))));
+}
+
+#[test]
+fn huge_zip64_compressed_size_is_refused() {
+    let mut extra = Vec::new();
+    extra.extend_from_slice(&0x0001u16.to_le_bytes());
+    extra.extend_from_slice(&8u16.to_le_bytes());
+    extra.extend_from_slice(&(u64::MAX - 10).to_le_bytes());
+    let mut bytes = common::build_raw_archive(&[RawEntry { extra_field: &extra, ..RawEntry::new(b"a", b"hello") }]).unwrap();
+    // The 32-bit compressed size in the central header defers to the zip64 field.
+    let central = bytes.windows(4).position(|window| window == 0x02014b50u32.to_le_bytes()).unwrap();
+    bytes[central + 20..central + 24].copy_from_slice(&u32::MAX.to_le_bytes());
+
+    let archive = ZipArchive::from_storage(Arc::new(bytes), ZipArchiveOptions::default()).unwrap();
+    assert!(matches!(archive.by_name("a"), Err(ZipError::InvalidArchive(_))));
+    assert!(matches!(archive.read_to_vec("a"), Err(ZipError::InvalidArchive(_))));
+}
+
+#[test]
+fn zip64_locator_is_found_before_a_long_comment() {
+    let archive = common::build_archive(&[("a.txt", common::STORED, b"alpha")], &[], false).unwrap();
+    for comment_length in [0, 65515, 65530, 65535] {
+        let bytes = common::with_zip64_end(archive.clone(), &vec![b'c'; comment_length]);
+        let archive = ZipArchive::from_storage(Arc::new(bytes), ZipArchiveOptions::default()).unwrap();
+        assert_eq!(archive.read_to_vec("a.txt").unwrap(), b"alpha");
+    }
+}
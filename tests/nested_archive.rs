@@ -0,0 +1,104 @@
+use std::io::Read;
+use std::sync::Arc;
+use vfs_rs::zip::result::{ZipError, ZipResult};
+use vfs_rs::zip::zip_archive::{ZipArchive, ZipArchiveOptions};
+
+mod common;
+
+fn open(bytes: Vec<u8>) -> ZipArchive {
+    ZipArchive::from_storage(Arc::new(bytes), ZipArchiveOptions::default()).unwrap()
+}
+
+fn read(archive: &ZipArchive, path: &str) -> ZipResult<Vec<u8>> {
+    let mut contents = Vec::new();
+    archive.by_path(path)?.read_to_end(&mut contents)?;
+    Ok(contents)
+}
+
+fn inner() -> Vec<u8> {
+    common::build_archive(
+        &[("a.txt", common::STORED, b"alpha"), ("dir/b.txt", common::DEFLATED, b"beta beta beta")],
+        &[],
+        false,
+    )
+    .unwrap()
+}
+
+#[test]
+fn stored_and_deflated_children_are_opened() {
+    let inner = inner();
+    for method in [common::STORED, common::DEFLATED] {
+        let outer = open(common::build_archive(&[("inner.zip", method, &inner)], &[], false).unwrap());
+
+        let nested = outer.open_nested("inner.zip").unwrap();
+        assert_eq!(nested.read_to_vec("a.txt").unwrap(), b"alpha");
+        assert_eq!(read(&outer, "inner.zip/a.txt").unwrap(), b"alpha");
+        assert_eq!(read(&outer, "inner.zip/dir/b.txt").unwrap(), b"beta beta beta");
+    }
+}
+
+#[test]
+fn paths_lead_through_several_archives() {
+    let middle = common::build_archive(&[("inner.zip", common::DEFLATED, &inner())], &[], false).unwrap();
+    let outer = open(
+        common::build_archive(
+            &[("mods/", common::STORED, b""), ("mods/outer.zip", common::STORED, &middle)],
+            &[],
+            false,
+        )
+        .unwrap(),
+    );
+    assert_eq!(read(&outer, "mods/outer.zip/inner.zip/dir/b.txt").unwrap(), b"beta beta beta");
+    assert!(matches!(read(&outer, "mods/outer.zip/inner.zip/missing.txt"), Err(ZipError::FileNotFound)));
+}
+
+#[test]
+fn paths_that_do_not_exist_are_not_found() {
+    let mut broken = inner();
+    // Starts like an archive but has no end of central directory record.
+    broken.truncate(40);
+    let outer = open(
+        common::build_archive(
+            &[
+                ("inner.zip", common::STORED, &inner()),
+                ("plain.txt", common::STORED, b"not an archive"),
+                ("packed.txt", common::DEFLATED, &b"not an archive either".repeat(100)),
+                ("short.txt", common::STORED, b"PK"),
+                ("broken.zip", common::STORED, &broken),
+            ],
+            &[],
+            false,
+        )
+        .unwrap(),
+    );
+    for path in [
+        "missing.zip/a.txt",
+        "inner.zip/missing.txt",
+        "inner.zip/dir",
+        "plain.txt/a.txt",
+        "packed.txt/a.txt",
+        "short.txt/a.txt",
+        "broken.zip/a.txt",
+    ] {
+        assert!(matches!(read(&outer, path), Err(ZipError::FileNotFound)), "{}", path);
+    }
+    // Asking for the same path again gives the same answer.
+    assert!(matches!(read(&outer, "broken.zip/a.txt"), Err(ZipError::FileNotFound)));
+    assert!(matches!(outer.mount("plain.txt"), Err(ZipError::InvalidArchive(_))));
+}
+
+#[test]
+fn mounts_are_kept_until_unmounted() {
+    let outer = open(common::build_archive(&[("inner.zip", common::STORED, &inner())], &[], false).unwrap());
+    assert!(!outer.unmount("inner.zip"));
+
+    let first = outer.mount("inner.zip").unwrap();
+    assert!(Arc::ptr_eq(&first, &outer.mount("inner.zip").unwrap()));
+    assert_eq!(read(&outer, "inner.zip/a.txt").unwrap(), b"alpha");
+
+    assert!(outer.unmount("inner.zip"));
+    assert!(!outer.unmount("inner.zip"));
+    // The earlier handle still reads, a new mount opens the archive again.
+    assert_eq!(first.read_to_vec("a.txt").unwrap(), b"alpha");
+    assert!(!Arc::ptr_eq(&first, &outer.mount("inner.zip").unwrap()));
+}
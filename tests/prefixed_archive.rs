@@ -0,0 +1,65 @@
+use std::io::Read;
+use vfs_rs::zip::zip_archive::ZipArchive;
+
+mod common;
+
+const ENTRIES: &[(&str, u16, &[u8])] = &[
+    ("assets/readme.txt", common::STORED, b"appended assets"),
+    ("assets/config.json", common::DEFLATED, b"{\"name\": \"stub\", \"values\": [1, 1, 1, 1, 1, 1]}"),
+];
+
+fn stub() -> Vec<u8> {
+    let mut stub = b"\x7fELF\x02\x01\x01\0".to_vec();
+    stub.extend((0..40_000u32).map(|i| (i * 31 % 251) as u8));
+    stub
+}
+
+fn check_entries(archive: &ZipArchive) {
+    for (name, _, contents) in ENTRIES {
+        assert_eq!(archive.read_to_vec(name).unwrap(), *contents);
+        let mut streamed = Vec::new();
+        archive.by_name(name).unwrap().read_to_end(&mut streamed).unwrap();
+        assert_eq!(streamed, *contents);
+    }
+}
+
+#[test]
+fn archive_appended_to_stub() {
+    let stub = stub();
+    let file = common::TempFile::new("appended.zip", &common::build_archive(ENTRIES, &stub, false).unwrap()).unwrap();
+    let archive = ZipArchive::new(&file.0).unwrap();
+
+    check_entries(&archive);
+    assert_eq!(archive.archive_offset, stub.len() as u64);
+    assert_eq!(archive.prefix_len(), stub.len() as u64);
+    let mut prefix = Vec::new();
+    archive.prefix_reader().read_to_end(&mut prefix).unwrap();
+    assert_eq!(prefix, stub);
+}
+
+#[test]
+fn archive_with_adjusted_offsets() {
+    let stub = stub();
+    let file = common::TempFile::new("adjusted.zip", &common::build_archive(ENTRIES, &stub, true).unwrap()).unwrap();
+    let archive = ZipArchive::new(&file.0).unwrap();
+
+    check_entries(&archive);
+    assert_eq!(archive.archive_offset, 0);
+    assert_eq!(archive.prefix_len(), stub.len() as u64);
+    let mut prefix = Vec::new();
+    archive.prefix_reader().read_to_end(&mut prefix).unwrap();
+    assert_eq!(prefix, stub);
+}
+
+#[test]
+fn archive_without_prefix() {
+    let file = common::TempFile::new("plain.zip", &common::build_archive(ENTRIES, &[], false).unwrap()).unwrap();
+    let archive = ZipArchive::new(&file.0).unwrap();
+
+    check_entries(&archive);
+    assert_eq!(archive.archive_offset, 0);
+    assert_eq!(archive.prefix_len(), 0);
+    let mut prefix = Vec::new();
+    archive.prefix_reader().read_to_end(&mut prefix).unwrap();
+    assert!(prefix.is_empty());
+}
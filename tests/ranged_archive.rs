@@ -0,0 +1,42 @@
+use std::io;
+use vfs_rs::zip::result::ZipError;
+use vfs_rs::zip::zip_archive::{ZipArchive, ZipArchiveOptions};
+
+mod common;
+
+#[test]
+fn archives_are_opened_from_ranges_of_a_container() {
+    let first = common::build_archive(&[("name.txt", common::STORED, b"first")], &[], false).unwrap();
+    let second = common::build_archive(&[("name.txt", common::DEFLATED, b"second")], &[], false).unwrap();
+    // Bytes that look like the end of an archive around both, to tell whether they stay invisible.
+    let filler = [b"header ".as_slice(), &0x06054b50u32.to_le_bytes(), &[0; 18]].concat();
+    let container = [&filler[..], &first, &filler, &second, &filler].concat();
+    let file = common::TempFile::new("container.bin", &container).unwrap();
+
+    let ranges = [
+        (filler.len(), first.len(), b"first".as_slice()),
+        (2 * filler.len() + first.len(), second.len(), b"second"),
+    ];
+    for (offset, len, contents) in ranges {
+        let archive = ZipArchive::with_range(&file.0, offset as u64, len as u64, ZipArchiveOptions::default()).unwrap();
+        assert_eq!(archive.entries.len(), 1);
+        assert_eq!(archive.read_to_vec("name.txt").unwrap(), contents);
+        assert_eq!(archive.prefix_len(), 0);
+    }
+}
+
+#[test]
+fn ranges_past_the_end_are_refused() {
+    let archive = common::build_archive(&[("a.txt", common::STORED, b"alpha")], &[], false).unwrap();
+    let file = common::TempFile::new("short-container.bin", &archive).unwrap();
+    let len = archive.len() as u64;
+
+    assert!(ZipArchive::with_range(&file.0, 0, len, ZipArchiveOptions::default()).is_ok());
+    for (offset, len) in [(1, len), (0, len + 1), (len + 1, 0), (u64::MAX, 2)] {
+        match ZipArchive::with_range(&file.0, offset, len, ZipArchiveOptions::default()) {
+            Err(ZipError::Io(e)) => assert_eq!(e.kind(), io::ErrorKind::InvalidInput),
+            Err(e) => panic!("unexpected error {}", e),
+            Ok(_) => panic!("range {}+{} was opened", offset, len),
+        }
+    }
+}
@@ -0,0 +1,116 @@
+use std::io;
+use std::io::Read;
+use std::sync::Arc;
+use vfs_rs::zip::codec::Codec;
+use vfs_rs::zip::entry_cache::EntryCache;
+use vfs_rs::zip::result::ZipError;
+use vfs_rs::zip::zip_archive::{ZipArchive, ZipArchiveOptions};
+use vfs_rs::zip::zip_file::ZipFileData;
+
+mod common;
+
+const INVERTED: u16 = 99;
+
+// Stores every byte inverted, enough to tell a codec apart from stored data.
+struct InvertCodec;
+
+impl Codec for InvertCodec {
+    fn decoder(&self, mut reader: Box<dyn Read + Send>, _data: &ZipFileData) -> io::Result<Box<dyn Read + Send>> {
+        let mut contents = Vec::new();
+        reader.read_to_end(&mut contents)?;
+        contents.iter_mut().for_each(|b| *b = !*b);
+        Ok(Box::new(io::Cursor::new(contents)))
+    }
+}
+
+fn open(bytes: Vec<u8>, options: ZipArchiveOptions) -> ZipArchive {
+    ZipArchive::from_storage(Arc::new(bytes), options).unwrap()
+}
+
+fn text() -> Vec<u8> {
+    b"deflated text, ".repeat(200)
+}
+
+#[test]
+fn entries_are_read_into_the_start_of_the_buffer() {
+    let bytes = common::build_archive(
+        &[("stored.txt", common::STORED, b"stored"), ("deflated.txt", common::DEFLATED, &text())],
+        &[],
+        false,
+    )
+    .unwrap();
+    for entry_cache in [None, Some(EntryCache::new(1 << 20))] {
+        let archive = open(bytes.clone(), ZipArchiveOptions { entry_cache, ..ZipArchiveOptions::default() });
+
+        let mut buf = vec![0xAA; 4000];
+        assert_eq!(archive.read_into("stored.txt", &mut buf).unwrap(), 6);
+        assert_eq!(&buf[..6], b"stored");
+        assert_eq!(buf[6], 0xAA);
+        assert_eq!(archive.read_into("deflated.txt", &mut buf).unwrap(), text().len());
+        assert_eq!(&buf[..text().len()], text());
+        assert_eq!(buf[text().len()], 0xAA);
+
+        let mut exact = vec![0; text().len()];
+        assert_eq!(archive.read_into("deflated.txt", &mut exact).unwrap(), text().len());
+        assert!(matches!(archive.read_into("missing.txt", &mut buf), Err(ZipError::FileNotFound)));
+    }
+}
+
+#[test]
+fn buffer_smaller_than_the_entry_is_refused() {
+    let bytes = common::build_archive(&[("deflated.txt", common::DEFLATED, &text())], &[], false).unwrap();
+    let archive = open(bytes, ZipArchiveOptions::default());
+    let mut buf = vec![0; text().len() - 1];
+    match archive.read_into("deflated.txt", &mut buf) {
+        Err(ZipError::Io(e)) => assert_eq!(e.kind(), io::ErrorKind::InvalidInput),
+        Err(e) => panic!("unexpected error {}", e),
+        Ok(_) => panic!("entry was read into a short buffer"),
+    }
+}
+
+#[test]
+fn entries_shorter_than_declared_are_cut_off() {
+    for method in [common::STORED, common::DEFLATED] {
+        let bytes = common::build_archive(&[("short.txt", method, &text())], &[], false).unwrap();
+        let declared = text().len() + 100;
+        let archive = open(common::set_uncompressed_size(bytes, declared as u32), ZipArchiveOptions::default());
+
+        let mut buf = vec![0; declared];
+        match archive.read_into("short.txt", &mut buf) {
+            Err(ZipError::Io(e)) => assert_eq!(e.kind(), io::ErrorKind::UnexpectedEof),
+            Err(e) => panic!("unexpected error {}", e),
+            Ok(_) => panic!("entry shorter than declared was read"),
+        }
+    }
+}
+
+#[test]
+fn entries_longer_than_declared_are_refused() {
+    let bytes = common::build_archive(&[("long.txt", common::DEFLATED, &text())], &[], false).unwrap();
+    let archive = open(common::set_uncompressed_size(bytes, 100), ZipArchiveOptions::default());
+    let mut buf = vec![0; text().len()];
+    assert!(matches!(archive.read_into("long.txt", &mut buf), Err(ZipError::EntrySizeExceeded(_))));
+}
+
+#[test]
+fn registered_codecs_are_used() {
+    let inverted: Vec<u8> = b"through a codec".iter().map(|b| !b).collect();
+    let bytes = common::build_archive(
+        &[("custom.bin", INVERTED, &inverted), ("stored.bin", common::STORED, &inverted)],
+        &[],
+        false,
+    )
+    .unwrap();
+    let mut archive = open(bytes, ZipArchiveOptions::default());
+    let mut buf = vec![0; 100];
+    assert!(matches!(archive.read_into("custom.bin", &mut buf), Err(ZipError::UnsupportedEntry(_, INVERTED))));
+
+    archive.codecs.register(INVERTED, InvertCodec);
+    assert_eq!(archive.read_into("custom.bin", &mut buf).unwrap(), 15);
+    assert_eq!(&buf[..15], b"through a codec");
+
+    // A codec registered for a builtin method replaces it.
+    archive.codecs.register(common::STORED, InvertCodec);
+    assert_eq!(archive.read_into("stored.bin", &mut buf).unwrap(), 15);
+    assert_eq!(&buf[..15], b"through a codec");
+}
@@ -0,0 +1,79 @@
+use std::fs;
+use std::io::Write;
+use std::sync::Arc;
+use vfs_rs::zip::result::ZipError;
+use vfs_rs::zip::spec::CompressionMethod;
+use vfs_rs::zip::storage::Storage;
+use vfs_rs::zip::zip_archive::{ZipArchive, ZipArchiveOptions};
+use vfs_rs::zip::zip_writer::{FileOptions, ZipWriter, ZipWriterOptions};
+
+mod common;
+
+fn contents(seed: u8) -> Vec<u8> {
+    (0..60_000u32).map(|i| (i.wrapping_mul(2654435761) >> 13) as u8 ^ seed).collect()
+}
+
+// Writes `archive.zip` to `dir`, split into 64 KiB disks when `split` is set. Returns the number of
+// disks.
+fn write_archive(dir: &std::path::Path, split: bool) -> u32 {
+    let options = ZipWriterOptions { split_size: if split { 64 * 1024 } else { 0 }, ..ZipWriterOptions::default() };
+    let stored = FileOptions { compression_method: CompressionMethod::Stored, ..FileOptions::default() };
+    let mut writer = ZipWriter::create(dir.join("archive.zip"), options).unwrap();
+    for i in 0..4 {
+        writer.start_file(format!("{}.bin", i), stored.clone()).unwrap();
+        writer.write_all(&contents(i)).unwrap();
+    }
+    writer.finish().unwrap()
+}
+
+fn check_contents(archive: &ZipArchive) {
+    assert_eq!(archive.entries.len(), 4);
+    for i in 0..4 {
+        assert_eq!(archive.read_to_vec(&format!("{}.bin", i)).unwrap(), contents(i));
+    }
+}
+
+#[test]
+fn disks_are_read_together() {
+    let dir = common::TempDir::new("split-disks").unwrap();
+    let disks = write_archive(&dir.0, true);
+    assert_eq!(disks, 4);
+    check_contents(&ZipArchive::open_split(dir.0.join("archive.zip"), ZipArchiveOptions::default()).unwrap());
+
+    let parts: Vec<Arc<dyn Storage>> = ["archive.z01", "archive.z02", "archive.z03", "archive.zip"]
+        .iter()
+        .map(|name| Arc::new(fs::read(dir.0.join(name)).unwrap()) as Arc<dyn Storage>)
+        .collect();
+    check_contents(&ZipArchive::from_disks(parts, ZipArchiveOptions::default()).unwrap());
+}
+
+#[test]
+fn byte_splits_are_joined() {
+    let dir = common::TempDir::new("split-bytes").unwrap();
+    write_archive(&dir.0, false);
+    let whole = fs::read(dir.0.join("archive.zip")).unwrap();
+    fs::remove_file(dir.0.join("archive.zip")).unwrap();
+    for (i, part) in whole.chunks(100_000).enumerate() {
+        fs::write(dir.0.join(format!("archive.zip.{:03}", i + 1)), part).unwrap();
+    }
+    assert!(dir.0.join("archive.zip.003").exists());
+
+    // Any part names the whole set.
+    for part in ["archive.zip.001", "archive.zip.002"] {
+        check_contents(&ZipArchive::open_split(dir.0.join(part), ZipArchiveOptions::default()).unwrap());
+    }
+}
+
+#[test]
+fn missing_disks_are_reported() {
+    let dir = common::TempDir::new("split-missing").unwrap();
+    write_archive(&dir.0, true);
+    fs::remove_file(dir.0.join("archive.z02")).unwrap();
+    let result = ZipArchive::open_split(dir.0.join("archive.zip"), ZipArchiveOptions::default());
+    assert!(matches!(result, Err(ZipError::MissingDisk(2))));
+
+    // Without any of the other disks, the last one alone is not enough either.
+    fs::remove_file(dir.0.join("archive.z01")).unwrap();
+    let result = ZipArchive::open_split(dir.0.join("archive.zip"), ZipArchiveOptions::default());
+    assert!(matches!(result, Err(ZipError::UnsupportedArchive)));
+}
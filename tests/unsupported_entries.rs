@@ -0,0 +1,81 @@
+use std::io;
+use std::io::Read;
+use std::sync::Arc;
+use vfs_rs::zip::codec::Codec;
+use vfs_rs::zip::result::ZipError;
+use vfs_rs::zip::zip_archive::{ZipArchive, ZipArchiveOptions};
+use vfs_rs::zip::zip_file::ZipFileData;
+
+mod common;
+
+// Private method ids no codec is built in for.
+const TEXTURE: u16 = 77;
+const OTHER: u16 = 199;
+
+struct IdentityCodec;
+
+impl Codec for IdentityCodec {
+    fn decoder(&self, reader: Box<dyn Read + Send>, _data: &ZipFileData) -> io::Result<Box<dyn Read + Send>> {
+        Ok(reader)
+    }
+}
+
+fn open() -> ZipArchive {
+    let bytes = common::build_archive(
+        &[
+            ("plain.txt", common::STORED, b"plain"),
+            ("textures/a.tex", TEXTURE, b"texture a"),
+            ("textures/b.tex", TEXTURE, b"texture b"),
+            ("other.bin", OTHER, b"other"),
+        ],
+        &[],
+        false,
+    )
+    .unwrap();
+    ZipArchive::from_storage(Arc::new(bytes), ZipArchiveOptions::default()).unwrap()
+}
+
+fn unsupported_names(archive: &ZipArchive) -> Vec<&str> {
+    let mut names: Vec<&str> = archive.unsupported_entries().map(|data| data.file_name.as_str()).collect();
+    names.sort();
+    names
+}
+
+#[test]
+fn unsupported_entries_are_listed() {
+    let archive = open();
+    assert_eq!(archive.entries.len(), 4);
+    assert!(archive.file_exist("textures/a.tex"));
+    assert_eq!(unsupported_names(&archive), ["other.bin", "textures/a.tex", "textures/b.tex"]);
+
+    assert!(archive.is_supported(&archive.entries["plain.txt"]));
+    assert!(!archive.is_supported(&archive.entries["other.bin"]));
+    assert_eq!(archive.entries["textures/a.tex"].compression_method.to_u16(), TEXTURE);
+    assert_eq!(archive.entries["textures/a.tex"].uncompressed_size, 9);
+}
+
+#[test]
+fn reading_unsupported_entries_names_them() {
+    let archive = open();
+    assert_eq!(archive.read_to_vec("plain.txt").unwrap(), b"plain");
+
+    match archive.by_name("textures/a.tex") {
+        Err(ZipError::UnsupportedEntry(name, TEXTURE)) => assert_eq!(name, "textures/a.tex"),
+        Err(e) => panic!("unexpected error {}", e),
+        Ok(_) => panic!("unsupported entry was opened"),
+    }
+    let error = archive.read_to_vec("other.bin").unwrap_err();
+    assert!(matches!(&error, ZipError::UnsupportedEntry(name, OTHER) if name == "other.bin"));
+    assert!(error.to_string().contains("other.bin"));
+    assert!(matches!(archive.read_shared("other.bin"), Err(ZipError::UnsupportedEntry(_, OTHER))));
+    assert!(matches!(archive.read_into("other.bin", &mut [0; 10]), Err(ZipError::UnsupportedEntry(_, OTHER))));
+}
+
+#[test]
+fn registering_a_codec_supports_its_entries() {
+    let mut archive = open();
+    archive.codecs.register(TEXTURE, IdentityCodec);
+    assert_eq!(unsupported_names(&archive), ["other.bin"]);
+    assert!(archive.is_supported(&archive.entries["textures/b.tex"]));
+    assert_eq!(archive.read_to_vec("textures/b.tex").unwrap(), b"texture b");
+}
@@ -0,0 +1,47 @@
+#![cfg(feature = "xz")]
+
+use std::io::{Read, Write};
+use std::sync::Arc;
+use xz2::write::XzEncoder;
+use vfs_rs::zip::zip_archive::{ZipArchive, ZipArchiveOptions};
+
+mod common;
+
+const XZ: u16 = 95;
+
+fn contents() -> Vec<u8> {
+    b"xz compressed contents ".repeat(500)
+}
+
+fn compress(preset: u32) -> Vec<u8> {
+    let mut encoder = XzEncoder::new(Vec::new(), preset);
+    encoder.write_all(&contents()).unwrap();
+    encoder.finish().unwrap()
+}
+
+fn open(compressed: &[u8]) -> ZipArchive {
+    let bytes = common::build_archive(&[("xz.bin", XZ, compressed)], &[], false).unwrap();
+    // The builder records the size of what it was given, the entry holds more once decompressed.
+    let bytes = common::set_uncompressed_size(bytes, contents().len() as u32);
+    ZipArchive::from_storage(Arc::new(bytes), ZipArchiveOptions::default()).unwrap()
+}
+
+#[test]
+fn xz_entries_are_decompressed() {
+    for preset in [1, 9] {
+        let archive = open(&compress(preset));
+        assert!(archive.is_supported(&archive.entries["xz.bin"]));
+
+        let mut streamed = Vec::new();
+        archive.by_name("xz.bin").unwrap().read_to_end(&mut streamed).unwrap();
+        assert_eq!(streamed, contents());
+        assert_eq!(archive.read_to_vec("xz.bin").unwrap(), contents());
+    }
+}
+
+#[test]
+fn corrupt_xz_entries_fail_to_read() {
+    let mut compressed = compress(6);
+    compressed[0] ^= 0xFF;
+    assert!(open(&compressed).read_to_vec("xz.bin").is_err());
+}
@@ -0,0 +1,156 @@
+use std::fs;
+use std::io::{Read, Write};
+use vfs_rs::zip::spec::CompressionMethod;
+use vfs_rs::zip::zip_archive::{ZipArchive, ZipArchiveOptions};
+use vfs_rs::zip::zip_writer::{FileOptions, ZipWriter, ZipWriterOptions};
+
+mod common;
+
+const SEGMENT: u64 = 64 * 1024;
+
+// Deterministic bytes that deflate cannot shrink.
+fn noise(len: usize, seed: u32) -> Vec<u8> {
+    let mut state = seed | 1;
+    (0..len)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state as u8
+        })
+        .collect()
+}
+
+fn options(method: CompressionMethod) -> FileOptions {
+    FileOptions { compression_method: method, ..FileOptions::default() }
+}
+
+fn write(path: &std::path::Path, split_size: u64, entries: &[(&str, CompressionMethod, &[u8])]) -> u32 {
+    let mut writer = ZipWriter::create(path, ZipWriterOptions { split_size, ..ZipWriterOptions::default() }).unwrap();
+    for (name, method, contents) in entries {
+        writer.start_file(*name, options(*method)).unwrap();
+        writer.write_all(contents).unwrap();
+    }
+    writer.finish().unwrap()
+}
+
+#[test]
+fn split_archive_round_trips() {
+    let dir = common::TempDir::new("writer-split").unwrap();
+    let path = dir.0.join("archive.zip");
+    let entries: Vec<(String, Vec<u8>)> = (0..12).map(|i| (format!("file{}.bin", i), noise(40_000, i))).collect();
+    let borrowed: Vec<(&str, CompressionMethod, &[u8])> = entries
+        .iter()
+        .enumerate()
+        .map(|(i, (name, contents))| {
+            let method = if i % 2 == 0 { CompressionMethod::Stored } else { CompressionMethod::Deflate };
+            (name.as_str(), method, contents.as_slice())
+        })
+        .collect();
+
+    let segments = write(&path, SEGMENT, &borrowed);
+    assert!(segments > 5);
+    for disk in 1..segments {
+        let segment = dir.0.join(format!("archive.z{:02}", disk));
+        assert!(fs::metadata(&segment).unwrap().len() <= SEGMENT);
+    }
+    assert_eq!(&fs::read(dir.0.join("archive.z01")).unwrap()[..4], &0x08074b50u32.to_le_bytes());
+    assert!(!dir.0.join(format!("archive.z{:02}", segments)).exists());
+
+    let archive = ZipArchive::open_split(&path, ZipArchiveOptions::default()).unwrap();
+    assert_eq!(archive.entries.len(), entries.len());
+    assert_eq!(archive.prefix_len(), 0);
+    for (name, contents) in &entries {
+        assert_eq!(&archive.read_to_vec(name).unwrap(), contents);
+    }
+}
+
+#[test]
+fn entries_cross_segment_boundaries() {
+    for method in [CompressionMethod::Stored, CompressionMethod::Deflate] {
+        let dir = common::TempDir::new("writer-boundary").unwrap();
+        let path = dir.0.join("archive.zip");
+        let first = noise(50_000, 1);
+        let second = noise(50_000, 2);
+        let segments = write(&path, SEGMENT, &[("first.bin", method, &first), ("second.bin", method, &second)]);
+        assert_eq!(segments, 2);
+
+        let archive = ZipArchive::open_split(&path, ZipArchiveOptions::default()).unwrap();
+        // The second entry starts on the first disk and continues on the next one.
+        assert_eq!(archive.entries["second.bin"].disk_number, 0);
+        assert_eq!(archive.read_to_vec("first.bin").unwrap(), first);
+        assert_eq!(archive.read_to_vec("second.bin").unwrap(), second);
+    }
+}
+
+#[test]
+fn single_segment_is_marked() {
+    let dir = common::TempDir::new("writer-single").unwrap();
+    let path = dir.0.join("archive.zip");
+    let segments = write(
+        &path,
+        SEGMENT,
+        &[("a.txt", CompressionMethod::Stored, b"alpha"), ("b.txt", CompressionMethod::Deflate, b"beta")],
+    );
+    assert_eq!(segments, 1);
+    assert!(!dir.0.join("archive.z01").exists());
+    assert_eq!(&fs::read(&path).unwrap()[..4], b"PK00");
+
+    let archive = ZipArchive::open_split(&path, ZipArchiveOptions::default()).unwrap();
+    assert_eq!(archive.read_to_vec("a.txt").unwrap(), b"alpha");
+    assert_eq!(archive.read_to_vec("b.txt").unwrap(), b"beta");
+    // The marker belongs to the archive, nothing was prepended to it.
+    for archive in [archive, ZipArchive::new(&path).unwrap()] {
+        assert_eq!(archive.prefix_len(), 0);
+        let mut prefix = Vec::new();
+        archive.prefix_reader().read_to_end(&mut prefix).unwrap();
+        assert!(prefix.is_empty());
+    }
+}
+
+#[test]
+fn unsplit_archive_has_no_marker() {
+    let dir = common::TempDir::new("writer-unsplit").unwrap();
+    let path = dir.0.join("archive.zip");
+    assert_eq!(write(&path, 0, &[("a.txt", CompressionMethod::Stored, b"alpha")]), 1);
+    assert_eq!(&fs::read(&path).unwrap()[..4], &0x04034b50u32.to_le_bytes());
+    assert_eq!(ZipArchive::new(&path).unwrap().read_to_vec("a.txt").unwrap(), b"alpha");
+}
+
+fn has_zip64_end(path: &std::path::Path) -> bool {
+    fs::read(path).unwrap().windows(4).any(|window| window == 0x06064b50u32.to_le_bytes())
+}
+
+// Only the entry count can reach a zip64 threshold in a test, the size and offset thresholds would
+// need 4 GiB of output.
+#[test]
+fn zip64_records_start_at_65535_entries() {
+    for (count, zip64) in [(65534, false), (65535, true)] {
+        let dir = common::TempDir::new("writer-zip64").unwrap();
+        let path = dir.0.join("archive.zip");
+        let mut writer = ZipWriter::create(&path, ZipWriterOptions::default()).unwrap();
+        for i in 0..count {
+            writer.start_file(format!("{}", i), options(CompressionMethod::Stored)).unwrap();
+        }
+        writer.finish().unwrap();
+
+        assert_eq!(has_zip64_end(&path), zip64);
+        let archive = ZipArchive::new(&path).unwrap();
+        assert_eq!(archive.entries.len(), count);
+    }
+}
+
+#[test]
+fn large_file_entries_round_trip() {
+    let dir = common::TempDir::new("writer-large-file").unwrap();
+    let path = dir.0.join("archive.zip");
+    let contents = noise(10_000, 3);
+    let mut writer = ZipWriter::create(&path, ZipWriterOptions::default()).unwrap();
+    writer
+        .start_file("large.bin", FileOptions { large_file: true, ..FileOptions::default() })
+        .unwrap();
+    writer.write_all(&contents).unwrap();
+    writer.finish().unwrap();
+
+    assert_eq!(ZipArchive::new(&path).unwrap().read_to_vec("large.bin").unwrap(), contents);
+}
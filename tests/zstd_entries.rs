@@ -0,0 +1,40 @@
+#![cfg(feature = "zstd")]
+
+use std::io::Read;
+use std::sync::Arc;
+use vfs_rs::zip::zip_archive::{ZipArchive, ZipArchiveOptions};
+
+mod common;
+
+const ZSTD: u16 = 93;
+
+fn contents() -> Vec<u8> {
+    b"zstd compressed contents ".repeat(500)
+}
+
+fn open(compressed: &[u8]) -> ZipArchive {
+    let bytes = common::build_archive(&[("zstd.bin", ZSTD, compressed)], &[], false).unwrap();
+    // The builder records the size of what it was given, the entry holds more once decompressed.
+    let bytes = common::set_uncompressed_size(bytes, contents().len() as u32);
+    ZipArchive::from_storage(Arc::new(bytes), ZipArchiveOptions::default()).unwrap()
+}
+
+#[test]
+fn zstd_entries_are_decompressed() {
+    for level in [1, 19] {
+        let archive = open(&zstd::encode_all(&contents()[..], level).unwrap());
+        assert!(archive.is_supported(&archive.entries["zstd.bin"]));
+
+        let mut streamed = Vec::new();
+        archive.by_name("zstd.bin").unwrap().read_to_end(&mut streamed).unwrap();
+        assert_eq!(streamed, contents());
+        assert_eq!(archive.read_to_vec("zstd.bin").unwrap(), contents());
+    }
+}
+
+#[test]
+fn corrupt_zstd_entries_fail_to_read() {
+    let mut compressed = zstd::encode_all(&contents()[..], 3).unwrap();
+    compressed[0] ^= 0xFF;
+    assert!(open(&compressed).read_to_vec("zstd.bin").is_err());
+}